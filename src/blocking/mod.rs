@@ -0,0 +1,145 @@
+//! A synchronous client for apps without an async runtime.
+//!
+//! Only available with the `blocking` feature. It's built on [std::net::UdpSocket] and returns
+//! the same models as the async [Client](crate::Client), but leaves out its extras (retries,
+//! token caching, timings) to stay small.
+
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::{encode, parse, utils, Error, Result};
+use crate::model::{packet, LongQuery, RakNetPong, ShortQuery};
+
+/// A blocking query client bound to a local udp socket.
+///
+/// # [Example]
+/// ```no_run
+/// use rsquery::blocking::Client;
+///
+/// fn main() -> rsquery::Result<()> {
+///     let client = Client::new("ip:port")?;
+///     println!("{:?}", client.raknet_ping()?);
+///     Ok(())
+/// }
+/// ```
+pub struct Client<A: ToSocketAddrs> {
+    socket: UdpSocket,
+    remote: A,
+    max_response: usize,
+    /// What replies are received into, kept rather than allocated for every query.
+    buf: Mutex<Box<[u8]>>,
+}
+
+impl<A: ToSocketAddrs> Client<A> {
+    /// Constructs a new Client targeted to that said remote, bound to `0.0.0.0:0`, or to `[::]:0` if
+    /// the remote resolves to an IPv6 address.
+    ///
+    /// # [Errors]
+    /// - On bind failure
+    pub fn new(remote: A) -> Result<Self> {
+        let ipv6 = matches!(remote.to_socket_addrs().map(|mut addrs| addrs.next()), Ok(Some(SocketAddr::V6(_))));
+        Ok(Client {
+            socket: UdpSocket::bind(if ipv6 { "[::]:0" } else { "0.0.0.0:0" })?,
+            remote,
+            max_response: u16::MAX as usize,
+            buf: Mutex::new(vec![0u8; u16::MAX as usize].into_boxed_slice()),
+        })
+    }
+
+    /// Returns the given remote this client is currently pointing too
+    pub fn remote(&self) -> &A {
+        &self.remote
+    }
+
+    /// Sets the remote the next queries go to.
+    pub fn set_remote(&mut self, remote: A) {
+        self.remote = remote;
+    }
+
+    /// How long to wait for each response before failing with [Error::Timeout](crate::Error::Timeout),
+    /// None waits forever (the default).
+    ///
+    /// # [Errors]
+    /// - A zero duration, which the socket rejects
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        Ok(self.socket.set_read_timeout(timeout)?)
+    }
+
+    /// The largest response in bytes a query accepts, see [ClientBuilder::max_response](crate::ClientBuilder::max_response).
+    pub fn set_max_response(&mut self, size: usize) {
+        self.max_response = size;
+    }
+
+    /// Sends the request and returns the reply with where it came from.
+    ///
+    /// Replies left over from a query that timed out are discarded first, and so are datagrams
+    /// from any address other than the remote's, ones over the size limit and ones not accepted
+    /// while waiting for the reply.
+    fn exchange(&self, request: &[u8], accept: impl Fn(&[u8]) -> bool) -> Result<(Vec<u8>, SocketAddr)> {
+        let mut buf = self.buf.lock().unwrap();
+        // into the whole buffer, windows fails reads of datagrams that don't fit instead of truncating them
+        self.socket.set_nonblocking(true)?;
+        while self.socket.recv_from(&mut buf).is_ok() {}
+        self.socket.set_nonblocking(false)?;
+        let remote = self.remote.to_socket_addrs()?.next()
+            .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "Remote did not resolve to an address"))?;
+        self.socket.send_to(request, remote)?;
+        let timeout = self.socket.read_timeout()?;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let reply = loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) if utils::same_addr(from, remote) && len <= self.max_response && accept(&buf[..len]) => {
+                    break Ok((buf[..len].to_vec(), from));
+                },
+                // discarded, so only wait for what's left of the timeout
+                Ok(_) => match deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())) {
+                    Some(left) if left.is_zero() => break Err(Error::Timeout),
+                    Some(left) => self.socket.set_read_timeout(Some(left))?,
+                    None => {},
+                },
+                // unix reports a read timeout as WouldBlock, windows as TimedOut
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break Err(Error::Timeout),
+                Err(e) => break Err(e.into()),
+            }
+        };
+        self.socket.set_read_timeout(timeout)?;
+        reply
+    }
+
+    /// Same as [exchange](Client::exchange) for a stat request, discarding responses for other sessions.
+    ///
+    /// Those are late responses to earlier queries, see [Client::long_query](crate::Client::long_query).
+    fn exchange_stat(&self, sid: i32, token: i32, full: bool) -> Result<Vec<u8>> {
+        let session = (sid & packet::SESSION_ID_MASK).to_be_bytes();
+        let accept = |datagram: &[u8]| datagram.first() != Some(&packet::STAT) || datagram.get(1..5) == Some(&session[..]);
+        Ok(self.exchange(&encode::stat(sid, token, full), accept)?.0)
+    }
+
+    /// Blocking version of [Client::raknet_ping](crate::Client::raknet_ping)
+    pub fn raknet_ping(&self) -> Result<RakNetPong> {
+        let (reply, from) = self.exchange(&encode::ping(encode::unix_millis(), rand::random()), |_| true)?;
+        let mut pong = parse::parse_pong(&reply)?;
+        pong.remote = Some(from);
+        Ok(pong)
+    }
+
+    /// Blocking version of [Client::gen_challenge_token](crate::Client::gen_challenge_token)
+    pub fn gen_challenge_token(&self, sid: i32) -> Result<i32> {
+        parse::parse_challenge_token(&self.exchange(&encode::handshake(sid), |_| true)?.0)
+    }
+
+    /// Blocking version of [Client::short_query](crate::Client::short_query)
+    pub fn short_query(&self) -> Result<ShortQuery> {
+        let sid = rand::random();
+        let token = self.gen_challenge_token(sid)?;
+        parse::parse_short_stat(&self.exchange_stat(sid, token, false)?)
+    }
+
+    /// Blocking version of [Client::long_query](crate::Client::long_query)
+    pub fn long_query(&self) -> Result<LongQuery> {
+        let sid = rand::random();
+        let token = self.gen_challenge_token(sid)?;
+        parse::parse_full_stat(&self.exchange_stat(sid, token, true)?)
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::VecDeque;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::net::{ToSocketAddrs, UdpSocket};
+use tokio::task::JoinSet;
+use socket2::{Domain, Protocol, Socket, Type};
+use crate::{AsyncDatagram, Client, Result};
+use crate::model::RakNetPong;
+use crate::router::Router;
+
+/// The timeout new builders start with, see [ClientBuilder::set_default_timeout].
+static DEFAULT_TIMEOUT: RwLock<Option<Duration>> = RwLock::new(None);
+
+/// The closure set with [ClientBuilder::map_remote].
+#[derive(Clone)]
+pub(crate) struct MapRemote(Arc<dyn Fn(SocketAddr) -> Result<SocketAddr> + Send + Sync>);
+
+impl MapRemote {
+    pub(crate) fn apply(&self, remote: SocketAddr) -> Result<SocketAddr> {
+        (self.0)(remote)
+    }
+}
+
+impl std::fmt::Debug for MapRemote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MapRemote")
+    }
+}
+
+/// Configures and binds a [Client](crate::Client).
+///
+/// [Client::new](crate::Client::new) is the same as building with the defaults: bound to `0.0.0.0:0`
+/// (`[::]:0` for IPv6 remotes), no timeout (unless a [default timeout](crate::ClientBuilder::set_default_timeout) is set) and no retries.
+///
+/// # [Example]
+/// ```no_run
+/// # use rsquery::ClientBuilder;
+/// # use std::time::Duration;
+/// # #[tokio::main]
+/// # async fn main() -> rsquery::Result<()> {
+/// // Only one outbound port is allowed through the NAT, so always query from it.
+/// let client = ClientBuilder::new()
+///     .bind("0.0.0.0:40000".parse().unwrap())
+///     .timeout(Duration::from_secs(2))
+///     .retries(3)
+///     .build("ip:port").await?;
+/// let data = client.raknet_ping().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    bind: Option<SocketAddr>,
+    timeout: Option<Duration>,
+    retries: usize,
+    backoff: Duration,
+    jitter: bool,
+    recv_buffer: Option<usize>,
+    send_buffer: Option<usize>,
+    max_response: usize,
+    player_limit: usize,
+    legacy: bool,
+    pipeline: bool,
+    map_remote: Option<MapRemote>,
+    #[cfg(feature = "socks5")]
+    proxy: Option<SocketAddr>,
+    #[cfg(feature = "socks5")]
+    proxy_credentials: Option<(String, String)>,
+}
+
+impl ClientBuilder {
+    /// A builder with the default settings.
+    pub fn new() -> Self {
+        ClientBuilder {
+            bind: None,
+            timeout: *DEFAULT_TIMEOUT.read().unwrap(),
+            retries: 0,
+            backoff: Duration::from_millis(100),
+            jitter: true,
+            recv_buffer: None,
+            send_buffer: None,
+            max_response: u16::MAX as usize,
+            player_limit: usize::MAX,
+            legacy: false,
+            pipeline: false,
+            map_remote: None,
+            #[cfg(feature = "socks5")]
+            proxy: None,
+            #[cfg(feature = "socks5")]
+            proxy_credentials: None,
+        }
+    }
+
+    /// Sets the timeout every builder created afterwards starts with, None for no timeout (the default).
+    ///
+    /// Applies to clients of any remote type, made with [Client::new](crate::Client::new) or a
+    /// builder, whose [timeout](crate::ClientBuilder::timeout) overrides it.
+    /// Clients and builders that already exist keep their timeout.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::{Client, ClientBuilder};
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// // once at startup
+    /// ClientBuilder::set_default_timeout(Some(Duration::from_secs(2)));
+    /// // times out after 2 seconds
+    /// let data = Client::new("ip:port").await?.raknet_ping().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_default_timeout(timeout: Option<Duration>) {
+        *DEFAULT_TIMEOUT.write().unwrap() = timeout;
+    }
+
+    /// The local address to bind the socket to, a port of 0 lets the OS pick one.
+    ///
+    /// Unset, the socket is bound to any port on `0.0.0.0`, or on `[::]` if the remote resolves
+    /// to an IPv6 address when building.
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.bind = Some(addr);
+        self
+    }
+
+    /// How long to wait for each response before failing with [Error::Timeout](crate::Error::Timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How many times a query is resent after timing out before the timeout is returned.
+    ///
+    /// Only has an effect together with a [timeout](ClientBuilder::timeout). Retries back off
+    /// exponentially, see [backoff](ClientBuilder::backoff).
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// How long to wait before the first retry, doubling for every retry after it. Defaults to 100ms.
+    ///
+    /// It's the most a retry waits while [jitter](ClientBuilder::jitter) is on.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Whether to wait a random time up to the backoff before retrying instead of the whole backoff, on by default.
+    ///
+    /// Clients that start retrying together (e.g. after the server they're polling restarted) would
+    /// keep resending in lockstep otherwise, all hitting the recovering server at once.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The size of the socket's receive buffer (`SO_RCVBUF`), the OS default if unset.
+    ///
+    /// Pinging many servers at once can receive pongs faster than they're read, a bigger
+    /// buffer keeps those bursts from being dropped. The OS may round or cap the size.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer = Some(size);
+        self
+    }
+
+    /// The size of the socket's send buffer (`SO_SNDBUF`), the OS default if unset.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer = Some(size);
+        self
+    }
+
+    /// The largest response in bytes a query accepts, defaults to 65535 (the largest udp datagram).
+    ///
+    /// Bigger datagrams are discarded and the query keeps waiting, so a server that only sends
+    /// oversized ones times out. Lowering it keeps untrusted servers from handing back more than a
+    /// query expects, which adds up when scanning many at once.
+    pub fn max_response(mut self, size: usize) -> Self {
+        self.max_response = size;
+        self
+    }
+
+    /// The most player names a long query collects into [LongQuery::players](crate::model::LongQuery::players),
+    /// all of them by default.
+    ///
+    /// The player counts still report the totals. Busy servers send thousands of names, capping
+    /// them saves splitting and allocating the ones past the limit when only a few are wanted.
+    pub fn max_players_listed(mut self, count: usize) -> Self {
+        self.player_limit = count;
+        self
+    }
+
+    /// Whether to speak the legacy GS3 query protocol, for server software predating GS4 challenge tokens.
+    ///
+    /// Those servers, like very old PocketMine builds, don't answer handshakes. In legacy mode long and
+    /// short queries skip the handshake and send the stat request without a token, full stats ask for
+    /// everything with GS3's request flags instead of GS4's padding. Off by default.
+    /// [gen_challenge_token](crate::Client::gen_challenge_token) still sends a handshake.
+    pub fn legacy(mut self, legacy: bool) -> Self {
+        self.legacy = legacy;
+        self
+    }
+
+    /// Whether a long or short query whose cached challenge token expired tries the old token first.
+    ///
+    /// The stat request is sent with the expired token right away, together with the handshake for a
+    /// new one. Servers still accepting the old token (many accept the previous one for a while)
+    /// answer it without waiting for the handshake, saving a round trip every
+    /// [TOKEN_LIFETIME](crate::model::packet::TOKEN_LIFETIME) when polling a server. Servers that
+    /// drop it get the stat request again with the new token once the handshake is answered.
+    /// Off by default, since it sends a request more to those servers.
+    pub fn pipeline(mut self, pipeline: bool) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Sends every query through the udp relay of the SOCKS5 proxy at this address.
+    ///
+    /// Only available with the `socks5` feature. The udp association is set up when building,
+    /// and lasts as long as the client. Without a proxy queries are sent directly.
+    /// `Client::java_status` uses TCP and always connects directly.
+    #[cfg(feature = "socks5")]
+    pub fn socks5_proxy(mut self, proxy: SocketAddr) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// The username and password to authenticate to the [SOCKS5 proxy](ClientBuilder::socks5_proxy) with.
+    ///
+    /// Only available with the `socks5` feature. Without credentials no authentication is offered.
+    #[cfg(feature = "socks5")]
+    pub fn socks5_credentials(mut self, username: &str, password: &str) -> Self {
+        self.proxy_credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Rewrites or rejects the remote's address before every query, after it's (re)resolved.
+    ///
+    /// By default queries go to the first address the remote resolves to, resolving again for every
+    /// query. The map gets that address and returns the one to query instead, e.g. applying a NAT
+    /// mapping, or an error to fail the query with without sending anything.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::ClientBuilder;
+    /// # use std::net::SocketAddr;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// // the server is reachable on another port from inside the NAT
+    /// let client = ClientBuilder::new()
+    ///     .map_remote(|remote: SocketAddr| Ok(SocketAddr::new(remote.ip(), 19133)))
+    ///     .build("ip:port").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn map_remote<F>(mut self, map: F) -> Self
+    where F: Fn(SocketAddr) -> Result<SocketAddr> + Send + Sync + 'static
+    {
+        self.map_remote = Some(MapRemote(Arc::new(map)));
+        self
+    }
+
+    /// The configured bind address, or else the unspecified address of the family `remote` resolves
+    /// to (after the [remote map](ClientBuilder::map_remote)).
+    ///
+    /// Falls back to IPv4 if the remote doesn't resolve, the query reports that error later.
+    async fn bind_addr<A: ToSocketAddrs>(&self, remote: &A) -> SocketAddr {
+        if let Some(bind) = self.bind {
+            return bind;
+        }
+        let resolved = match &self.map_remote {
+            Some(map) => crate::utils::resolve(remote).await.and_then(|remote| map.apply(remote)),
+            None => crate::utils::resolve(remote).await,
+        };
+        match resolved {
+            Ok(SocketAddr::V6(_)) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+            _ => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        }
+    }
+
+    /// Whether the client's socket can reach the remote, a socket bound for one address family can't
+    /// reach the other. A proxied client always sends to its relay.
+    async fn fits<A: ToSocketAddrs>(&self, client: &Client<A>, remote: &A) -> bool {
+        #[cfg(feature = "socks5")]
+        if client.proxy.is_some() {
+            return true;
+        }
+        let bind = self.bind_addr(remote).await;
+        client.router.local_addr().is_ok_and(|local| local.is_ipv6() == bind.is_ipv6())
+    }
+
+    /// Binds a udp socket to the address with the configured buffer sizes.
+    pub(crate) fn bind_socket(&self, bind: SocketAddr) -> Result<UdpSocket> {
+        let socket = Socket::new(Domain::for_address(bind), Type::DGRAM, Some(Protocol::UDP))?;
+        if let Some(size) = self.recv_buffer {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send_buffer {
+            socket.set_send_buffer_size(size)?;
+        }
+        socket.set_nonblocking(true)?;
+        socket.bind(&bind.into())?;
+        Ok(UdpSocket::from_std(socket.into())?)
+    }
+
+    /// Binds the socket and constructs the client targeted to the remote.
+    ///
+    /// # [Errors]
+    /// - On bind failure
+    pub async fn build<A: ToSocketAddrs>(self, remote: A) -> Result<Client<A>> {
+        #[cfg(feature = "socks5")]
+        let proxy = match self.proxy {
+            Some(proxy) => Some(Arc::new(crate::socks5::Relay::associate(proxy, self.proxy_credentials.as_ref()).await?)),
+            None => None,
+        };
+        // queries are sent to the relay when proxied, so its family is the one that matters
+        #[cfg(feature = "socks5")]
+        let bind = match &proxy {
+            Some(relay) => self.bind_addr(&relay.addr).await,
+            None => self.bind_addr(&remote).await,
+        };
+        #[cfg(not(feature = "socks5"))]
+        let bind = self.bind_addr(&remote).await;
+        let socket = Arc::new(self.bind_socket(bind)?);
+        #[cfg(feature = "socks5")]
+        if let Some(relay) = &proxy {
+            let router = Router::relayed(socket, relay.addr);
+            return Ok(Client { proxy, ..self.client(router, remote) });
+        }
+        Ok(self.build_with_socket(socket, remote))
+    }
+
+    /// Constructs the client targeted to the remote around an already bound socket.
+    ///
+    /// The bind address, buffer sizes and proxy of this builder are ignored since the socket
+    /// is used as is, see [Client::from_socket](crate::Client::from_socket).
+    pub fn build_with_socket<A: ToSocketAddrs>(self, socket: Arc<UdpSocket>, remote: A) -> Client<A> {
+        self.build_with_transport(socket, remote)
+    }
+
+    /// Constructs the client targeted to the remote around a transport other than a udp socket.
+    ///
+    /// Same as [build_with_socket](ClientBuilder::build_with_socket), see [AsyncDatagram](crate::AsyncDatagram).
+    pub fn build_with_transport<A: ToSocketAddrs>(self, transport: Arc<dyn AsyncDatagram>, remote: A) -> Client<A> {
+        self.client(Router::new(transport), remote)
+    }
+
+    fn client<A: ToSocketAddrs>(self, router: Router, remote: A) -> Client<A> {
+        Client {
+            router: Arc::new(router),
+            remote,
+            identity: None,
+            timeout: self.timeout,
+            retries: self.retries,
+            backoff: self.backoff,
+            jitter: self.jitter,
+            max_response: self.max_response,
+            player_limit: self.player_limit,
+            legacy: self.legacy,
+            pipeline: self.pipeline,
+            map_remote: self.map_remote.clone(),
+            token: Mutex::new(None),
+            #[cfg(feature = "pcap")]
+            pcap: None,
+            #[cfg(feature = "socks5")]
+            proxy: None,
+        }
+    }
+
+    /// Resolves the remote once, then binds the socket and constructs the client targeted to it.
+    ///
+    /// See [Client::resolve](crate::Client::resolve).
+    pub async fn resolve<A: ToSocketAddrs>(self, remote: A) -> Result<Client<SocketAddr>> {
+        let remote = crate::utils::resolve(remote).await?;
+        self.build(remote).await
+    }
+
+    /// Raknet pings every address with clients built from this builder, at most `concurrency` at once.
+    ///
+    /// Every in-flight ping has its own client, which goes on to ping the next address once it's done,
+    /// so no more than `concurrency` local ports are bound (a client moving between IPv4 and IPv6
+    /// addresses is rebound). Set a [timeout](ClientBuilder::timeout)
+    /// or a single unreachable server holds up its client forever.
+    ///
+    /// The results are in the same order as the addresses.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::ClientBuilder;
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let servers = ["play.example.com:19132", "mc.example.net:19132"];
+    /// let builder = ClientBuilder::new().timeout(Duration::from_secs(2));
+    /// for (addr, pong) in builder.ping_many(&servers, 16).await {
+    ///     match pong {
+    ///         Ok(pong) => println!("{}: {}/{}", addr, pong.player_count, pong.max_player_count),
+    ///         Err(e) => println!("{}: {}", addr, e),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn ping_many<A>(&self, addrs: &[A], concurrency: usize) -> Vec<(A, Result<RakNetPong>)>
+    where A: ToSocketAddrs + Clone + Send + Sync + 'static
+    {
+        let queue = Arc::new(Mutex::new(addrs.iter().cloned().enumerate().collect::<VecDeque<_>>()));
+        let mut workers = JoinSet::new();
+        for _ in 0..concurrency.clamp(1, addrs.len().max(1)) {
+            let queue = queue.clone();
+            let builder = self.clone();
+            workers.spawn(async move {
+                let next = || queue.lock().unwrap().pop_front();
+                let mut client: Option<Client<A>> = None;
+                let mut results = Vec::new();
+                while let Some((i, addr)) = next() {
+                    let reusable = match &client {
+                        Some(client) => builder.fits(client, &addr).await,
+                        None => false,
+                    };
+                    let bound = match client.take() {
+                        Some(mut client) if reusable => {
+                            client.set_remote(addr.clone());
+                            Ok(client)
+                        },
+                        _ => builder.clone().build(addr.clone()).await,
+                    };
+                    let pong = match bound {
+                        Ok(bound) => {
+                            let pong = bound.raknet_ping().await;
+                            client = Some(bound);
+                            pong
+                        },
+                        Err(e) => Err(e),
+                    };
+                    results.push((i, addr, pong));
+                }
+                results
+            });
+        }
+        let mut results = Vec::with_capacity(addrs.len());
+        while let Some(done) = workers.join_next().await {
+            results.extend(done.expect("a ping_many worker panicked"));
+        }
+        results.sort_by_key(|(i, _, _)| *i);
+        results.into_iter().map(|(_, addr, pong)| (addr, pong)).collect()
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        ClientBuilder::new()
+    }
+}
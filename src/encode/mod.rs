@@ -0,0 +1,64 @@
+//! Builds the request datagrams, shared by the async and the blocking client.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::model::packet;
+
+/// The current time in milliseconds since the unix epoch, what pings are timestamped with.
+pub(crate) fn unix_millis() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+}
+
+/// A raknet unconnected ping from the given client GUID, sent at `time` (milliseconds since the unix epoch).
+pub(crate) fn ping(time: i64, client_id: u64) -> Vec<u8> {
+    //Initalize Buf with the ID_UNCONNECTED_PING
+    let mut buf: Vec<u8> = vec![packet::UNCONNECTED_PING];
+    //Write the time stamp
+    buf.extend_from_slice(&time.to_be_bytes());
+    //Hex literal for Offline Message Data ID
+    buf.extend_from_slice(&packet::OFFLINE_MESSAGE_DATA_ID);
+    //Write the client id
+    buf.extend_from_slice(&client_id.to_be_bytes());
+    buf
+}
+
+/// A GS3 handshake asking for a challenge token for the session.
+pub(crate) fn handshake(sid: i32) -> Vec<u8> {
+    //Writes query protocol magic to the buf always 0xFEFD
+    let mut buf = packet::MAGIC.to_be_bytes().to_vec();
+    //Sending a handshake so the server sends back a challenge token for our given session id (always 0x09)
+    buf.push(packet::HANDSHAKE);
+    //Writing the sid to the buf
+    buf.extend_from_slice(&(sid & packet::SESSION_ID_MASK).to_be_bytes());
+    buf
+}
+
+/// A GS3 stat request, the padding after the token is what asks for a full stat instead of a basic one.
+pub(crate) fn stat(sid: i32, challenge_token: i32, full: bool) -> Vec<u8> {
+    // Write Query Magic
+    let mut buf = packet::MAGIC.to_be_bytes().to_vec();
+    // Write STAT for the packet id
+    buf.push(packet::STAT);
+    // Write Session Id
+    buf.extend_from_slice(&(sid & packet::SESSION_ID_MASK).to_be_bytes());
+    // Write challenge token
+    buf.extend_from_slice(&challenge_token.to_be_bytes());
+    if full {
+        // Padding
+        buf.extend_from_slice(&[0x00; 4]);
+    }
+    buf
+}
+
+/// A legacy GS3 stat request, which has no challenge token since those only came with GS4.
+///
+/// A basic stat request ends right after the session id, a full one asks for everything with the
+/// [GS3 request flags](packet::LEGACY_FULL_STAT_FLAGS).
+pub(crate) fn legacy_stat(sid: i32, full: bool) -> Vec<u8> {
+    let mut buf = packet::MAGIC.to_be_bytes().to_vec();
+    buf.push(packet::STAT);
+    buf.extend_from_slice(&(sid & packet::SESSION_ID_MASK).to_be_bytes());
+    if full {
+        buf.extend_from_slice(&packet::LEGACY_FULL_STAT_FLAGS);
+    }
+    buf
+}
@@ -0,0 +1,65 @@
+use alloc::string::String;
+use core::fmt;
+use crate::model::packet;
+
+/// The error type returned by every rsquery query
+///
+/// Separates transport failures ([Io](Error::Io), [Timeout](Error::Timeout)) from servers
+/// sending something that couldn't be understood, so callers can decide what's worth retrying.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying socket failed
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The server didn't respond in time
+    Timeout,
+    /// The query was cancelled before it finished, see [cancellable](crate::cancellable)
+    Cancelled,
+    /// The server responded with a different packet than the one awaited
+    UnexpectedPacket { expected: u8, got: u8 },
+    /// The response didn't follow the protocol
+    MalformedResponse(String),
+    /// The response is missing a key every server is expected to send
+    MissingField(&'static str),
+    /// A field of the response couldn't be parsed into its type
+    ParseField { field: &'static str },
+}
+
+/// Shorthand for a Result with an [rsquery::Error](crate::Error)
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Timeout => write!(f, "Timed out waiting for a response"),
+            Error::Cancelled => write!(f, "The query was cancelled"),
+            // Picking the wrong protocol is a common mistake, so a raknet pong gets a hint towards raknet_ping
+            Error::UnexpectedPacket { expected, got: packet::UNCONNECTED_PONG } if *expected != packet::UNCONNECTED_PONG =>
+                write!(f, "Unexpected packet 0x{:02X} received while awaiting 0x{:02X}: \
+                    this is a raknet unconnected pong, so it looks like a bedrock server, try raknet_ping", packet::UNCONNECTED_PONG, expected),
+            Error::UnexpectedPacket { expected, got } => write!(f, "Unexpected packet 0x{:02X} received while awaiting 0x{:02X}", got, expected),
+            Error::MalformedResponse(reason) => write!(f, "Malformed response: {}", reason),
+            Error::MissingField(key) => write!(f, "Server response is missing the {} key", key),
+            Error::ParseField { field } => write!(f, "Failed to parse the {} field", field),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,177 @@
+//! The java edition server list ping, see <https://wiki.vg/Server_List_Ping>.
+//!
+//! Only available with the `java` feature. Unlike the bedrock queries this runs over TCP,
+//! every packet is framed as a VarInt length followed by a VarInt packet id and the payload.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::{Error, Result};
+use crate::model::JavaStatus;
+
+/// Asks for the status without committing to a protocol version.
+const ANY_PROTOCOL: i32 = -1;
+/// The handshake's next state for a status request.
+const STATUS_STATE: i32 = 1;
+/// The packet id of the handshake, the status request and the status response.
+const STATUS_PACKET: i32 = 0x00;
+/// The largest packet the protocol allows, anything longer is bogus.
+const MAX_PACKET_LEN: usize = (1 << 21) - 1;
+
+/// A remote that knows the host it was given as, sent in the server list ping's handshake.
+///
+/// Implemented for everything a [Client](crate::Client) can be made for. Proxies and servers behind
+/// a shared ip pick the backend by the host name, so a resolved ip wouldn't do.
+pub trait Host {
+    /// The host without its port (and without brackets around an IPv6 address).
+    fn host(&self) -> String;
+}
+
+impl Host for str {
+    fn host(&self) -> String {
+        let host = self.rsplit_once(':').map_or(self, |(host, _)| host);
+        host.strip_prefix('[').and_then(|host| host.strip_suffix(']')).unwrap_or(host).to_string()
+    }
+}
+
+impl Host for String {
+    fn host(&self) -> String {
+        self.as_str().host()
+    }
+}
+
+impl Host for SocketAddr {
+    fn host(&self) -> String {
+        self.ip().to_string()
+    }
+}
+
+impl Host for SocketAddrV4 {
+    fn host(&self) -> String {
+        self.ip().to_string()
+    }
+}
+
+impl Host for SocketAddrV6 {
+    fn host(&self) -> String {
+        self.ip().to_string()
+    }
+}
+
+impl Host for (IpAddr, u16) {
+    fn host(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl Host for (Ipv4Addr, u16) {
+    fn host(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl Host for (Ipv6Addr, u16) {
+    fn host(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl Host for (&str, u16) {
+    fn host(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl Host for (String, u16) {
+    fn host(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl<T: Host + ?Sized> Host for &T {
+    fn host(&self) -> String {
+        (**self).host()
+    }
+}
+
+/// Does the handshake and status request over the stream, returning the parsed status response.
+///
+/// The host and port are sent in the handshake, proxies use them to pick the backend server.
+pub(crate) async fn status<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, host: &str, port: u16) -> Result<JavaStatus> {
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, STATUS_PACKET);
+    write_varint(&mut handshake, ANY_PROTOCOL);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend_from_slice(host.as_bytes());
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, STATUS_STATE);
+    stream.write_all(&frame(&handshake)).await?;
+    let mut request = Vec::new();
+    write_varint(&mut request, STATUS_PACKET);
+    stream.write_all(&frame(&request)).await?;
+    // Reading
+    let len = read_varint(stream).await? as usize;
+    if len > MAX_PACKET_LEN {
+        return Err(Error::MalformedResponse(format!("Status response claims to be {} bytes long", len)));
+    }
+    let mut packet = vec![0u8; len];
+    stream.read_exact(&mut packet).await?;
+    parse_status(&packet)
+}
+
+/// Parses a status response packet, without its length prefix.
+pub(crate) fn parse_status(mut packet: &[u8]) -> Result<JavaStatus> {
+    match decode_varint(&mut packet)? {
+        STATUS_PACKET => {},
+        got => return Err(Error::UnexpectedPacket { expected: STATUS_PACKET as u8, got: got as u8 }),
+    }
+    let len = decode_varint(&mut packet)? as usize;
+    let json = packet.get(..len)
+        .ok_or_else(|| Error::MalformedResponse("Status response is shorter than its json".to_string()))?;
+    serde_json::from_slice(json).map_err(|e| Error::MalformedResponse(format!("Invalid status json: {}", e)))
+}
+
+fn frame(packet: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(packet.len() + 5);
+    write_varint(&mut framed, packet.len() as i32);
+    framed.extend_from_slice(packet);
+    framed
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+/// Reads a VarInt off the front of the bytes, advancing past it.
+fn decode_varint(bytes: &mut &[u8]) -> Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let (byte, rest) = bytes.split_first()
+            .ok_or_else(|| Error::MalformedResponse("Status response ends inside a VarInt".to_string()))?;
+        *bytes = rest;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(Error::MalformedResponse("VarInt is longer than 5 bytes".to_string()))
+}
+
+async fn read_varint<S: AsyncRead + Unpin>(stream: &mut S) -> Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let byte = stream.read_u8().await?;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(Error::MalformedResponse("VarInt is longer than 5 bytes".to_string()))
+}
@@ -1,47 +1,168 @@
-#![feature(async_closure)]
-
 //! An async minecraft query library implementing raknet pings and generic long querying.
 //!
 //! This crate is mainly meant for use with Minecraft Bedrock Edition, but is usable on java servers with a long query.
-//! Example
-//! ```no_run
-//! use rsquery::Client;
-//!
-//! #[tokio::main]
-//! async fn main() -> Result<(), Error> {
-//!    // Returns rsquery::model::ShortQuery which implements Debug.
-//!    println!("{:?}", Client::new("velvetpractice.live").await?.short_query().await?);
-//!    Ok(())
-//! }
-//! ```
+//! With the `java` feature java servers can also be queried over the server list ping, see `Client::java_status`.
+// the example needs the Client, which doesn't exist without std
+#![cfg_attr(feature = "std", doc = r#"Example
+```no_run
+use rsquery::Client;
+
+#[tokio::main]
+async fn main() -> rsquery::Result<()> {
+   // Returns rsquery::model::ShortQuery which implements Debug.
+   println!("{:?}", Client::new("velvetpractice.live").await?.short_query().await?);
+   Ok(())
+}
+```
+"#)]
 //! This crate works off of a custom Client struct and two response structs listed here:<br>
 //! [Client](crate::Client)<br>
 //! [ShortQuery](crate::model::ShortQuery)<br>
 //! [LongQuery](crate::model::LongQuery)<br>
+//!
+//! Without the default `std` feature the crate is `no_std` (it still needs `alloc`) and only has the
+//! [models](crate::model) and the [parsers](crate::parse), for decoding responses received some other way.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 use std::sync::Arc;
+#[cfg(feature = "std")]
 use tokio::net::{UdpSocket, ToSocketAddrs};
-use std::io::{Result, ErrorKind, Error, Write, Cursor};
-use hex::FromHex;
-use crate::model::{ShortQuery, LongQuery, packet, RakNetPong};
-use std::time::{SystemTime, UNIX_EPOCH};
-use byteorder::{WriteBytesExt, BigEndian, LittleEndian, ReadBytesExt};
-use rand::Rng;
-use std::str;
-use std::collections::HashMap;
-use tokio::sync::Mutex;
-use crate::utils::read_nulltermed_str;
-
-#[cfg(test)]
+#[cfg(feature = "std")]
+use std::net::SocketAddr;
+#[cfg(feature = "std")]
+use crate::model::{ShortQuery, LongQuery, packet, RakNetPong, ServerProfile};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "std")]
+use std::future::Future;
+#[cfg(feature = "std")]
+use tokio::sync::mpsc;
+#[cfg(feature = "std")]
+use tokio::time;
+#[cfg(feature = "std")]
+use tokio_stream::{Stream, StreamExt, wrappers::IntervalStream};
+
+/// Emits a tracing event with the `tracing` feature, expands to nothing without it.
+#[cfg(feature = "std")]
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    }};
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests;
 pub mod model;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod encode;
+mod error;
+pub mod parse;
+#[cfg(feature = "std")]
+mod query;
+#[cfg(feature = "std")]
+mod router;
 mod utils;
+#[cfg(feature = "std")]
+pub mod transport;
+#[cfg(feature = "pcap")]
+mod pcap;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "java")]
+mod java;
+#[cfg(feature = "socks5")]
+mod socks5;
 
+#[cfg(feature = "std")]
+pub use builder::ClientBuilder;
+pub use error::{Error, Result};
+#[cfg(feature = "std")]
+pub use query::Query;
+#[cfg(feature = "std")]
+pub use utils::cancellable;
+pub use utils::fmt_latency;
+#[cfg(feature = "std")]
+pub use transport::AsyncDatagram;
+#[cfg(feature = "java")]
+pub use java::Host;
+
+/// A query client bound to a local udp socket.
+///
+/// Queries only borrow the client, so one client can run any number of them at once (from
+/// several tasks too). A task reading the socket in the background hands every reply to the query
+/// waiting for it, matched by the address it came from and the session id (or ping timestamp) it echoes.
+///
+/// # [Cancellation]
+/// Every query method is cancellation safe. If a query future is dropped before its response
+/// arrived (e.g. when losing a `tokio::select!` race or a `tokio::time::timeout`) the late response
+/// is discarded when it arrives, no other query is waiting for it.
+/// [cancellable](crate::cancellable) runs a query against a cancel future, failing with
+/// [Error::Cancelled](crate::Error::Cancelled) when it's cancelled.
+#[cfg(feature = "std")]
 pub struct Client<A: ToSocketAddrs> {
-    socket: Arc<UdpSocket>,
+    router: Arc<router::Router>,
     remote: A,
+    identity: Option<u64>,
+    timeout: Option<Duration>,
+    retries: usize,
+    backoff: Duration,
+    /// Whether the wait before a retry is randomized, see [ClientBuilder::jitter](crate::ClientBuilder::jitter).
+    jitter: bool,
+    max_response: usize,
+    /// How many player names a long query collects at most.
+    player_limit: usize,
+    /// Whether stat requests skip the handshake, see [ClientBuilder::legacy](crate::ClientBuilder::legacy).
+    legacy: bool,
+    /// Whether an expired token is tried while a new one is fetched, see [ClientBuilder::pipeline](crate::ClientBuilder::pipeline).
+    pipeline: bool,
+    /// Rewrites the resolved remote before every query, see [ClientBuilder::map_remote](crate::ClientBuilder::map_remote).
+    map_remote: Option<builder::MapRemote>,
+    /// The session id of the last handshake and the challenge token it got, with when it was handed out.
+    token: std::sync::Mutex<Option<(i32, i32, Instant)>>,
+    #[cfg(feature = "pcap")]
+    pcap: Option<Arc<std::sync::Mutex<pcap::PcapWriter>>>,
+    /// The udp association datagrams are relayed through, None when sending directly.
+    #[cfg(feature = "socks5")]
+    proxy: Option<Arc<socks5::Relay>>,
+}
+
+/// Clones share the socket (and pcap writer), the remote and settings are copied.
+///
+/// Replies are routed to the query waiting for them, so clones can query at the same time, e.g.
+/// one per task. Every clone does its own handshake rather than sharing the cached challenge token.
+#[cfg(feature = "std")]
+impl<A: ToSocketAddrs + Clone> Clone for Client<A> {
+    fn clone(&self) -> Self {
+        Client {
+            router: self.router.clone(),
+            remote: self.remote.clone(),
+            identity: self.identity,
+            timeout: self.timeout,
+            retries: self.retries,
+            backoff: self.backoff,
+            jitter: self.jitter,
+            max_response: self.max_response,
+            player_limit: self.player_limit,
+            legacy: self.legacy,
+            pipeline: self.pipeline,
+            map_remote: self.map_remote.clone(),
+            token: std::sync::Mutex::new(None),
+            #[cfg(feature = "pcap")]
+            pcap: self.pcap.clone(),
+            #[cfg(feature = "socks5")]
+            proxy: self.proxy.clone(),
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl<A: ToSocketAddrs> Client<A> {
 
     /// Constructs a new Client targeted to that said remote.
@@ -50,6 +171,9 @@ impl<A: ToSocketAddrs> Client<A> {
     /// of now this struct keeps a locally binded socket open while it is in use.
     /// Meaning you have to await it and error check to see if the local socket successfully bound.
     ///
+    /// Uses the default settings, see [ClientBuilder](crate::ClientBuilder) for binding to a specific
+    /// address or setting a timeout.
+    ///
     /// # [Errors]
     /// - On bind failure
     ///
@@ -58,7 +182,7 @@ impl<A: ToSocketAddrs> Client<A> {
     /// use rsquery::Client;
     ///
     /// #[tokio::main]
-    /// async fn main() -> Result<(), Error> {
+    /// async fn main() -> rsquery::Result<()> {
     ///     let client = Client::new("ip:port").await?;
     ///     // Client successfully bound you can now safely use it
     ///     Ok(())
@@ -66,11 +190,51 @@ impl<A: ToSocketAddrs> Client<A> {
     /// }
     /// ```
     pub async fn new(remote: A) -> Result<Self> {
-        let socket =  Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
-        Ok(Client {
-            socket,
-            remote,
-        })
+        ClientBuilder::new().build(remote).await
+    }
+
+    /// Constructs a new Client targeted to the remote that uses the given socket instead of binding its own.
+    ///
+    /// Lets you apply socket options before handing it over. The socket must be bound already,
+    /// and anything else reading from it competes with the client for replies, another client made
+    /// from the same socket included. [Clone](Clone) the client to share the socket instead.
+    ///
+    /// Uses the default settings, see [ClientBuilder::build_with_socket](crate::ClientBuilder::build_with_socket)
+    /// to configure the rest of the client.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # use std::sync::Arc;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let socket = Arc::new(tokio::net::UdpSocket::bind("0.0.0.0:40000").await?);
+    /// socket.set_ttl(16)?;
+    /// let data = Client::from_socket(socket, "ip:port").raknet_ping().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_socket(socket: Arc<UdpSocket>, remote: A) -> Self {
+        ClientBuilder::new().build_with_socket(socket, remote)
+    }
+
+    /// Constructs a new Client targeted to the remote that sends and receives over the given transport.
+    ///
+    /// Same as [from_socket](crate::Client::from_socket) for transports other than a tokio udp socket,
+    /// see [AsyncDatagram](crate::AsyncDatagram).
+    pub fn from_transport(transport: Arc<dyn AsyncDatagram>, remote: A) -> Self {
+        ClientBuilder::new().build_with_transport(transport, remote)
+    }
+
+    /// Raknet pings every address with default clients, at most `concurrency` at once.
+    ///
+    /// The default clients have no timeout unless a [default timeout](crate::ClientBuilder::set_default_timeout)
+    /// is set, use [ClientBuilder::ping_many](crate::ClientBuilder::ping_many)
+    /// to ping with one (which pinging many servers almost always wants).
+    pub async fn ping_many(addrs: &[A], concurrency: usize) -> Vec<(A, Result<RakNetPong>)>
+    where A: Clone + Send + Sync + 'static
+    {
+        ClientBuilder::new().ping_many(addrs, concurrency).await
     }
 
     /// Returns the given remote this client is currently pointing too
@@ -84,6 +248,9 @@ impl<A: ToSocketAddrs> Client<A> {
     ///
     /// # [Example]
     /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
     /// let mut client = Client::new("ip:port").await?;
     /// // Short Query one server.
     /// let data1 = client.short_query().await?;
@@ -91,9 +258,288 @@ impl<A: ToSocketAddrs> Client<A> {
     /// client.set_remote("ip:port");
     /// // Long Query another server
     /// let data2 = client.long_query().await?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn set_remote(&mut self, remote: A) {
         self.remote = remote;
+        self.clear_token_cache();
+    }
+
+    /// Raknet pings the given remote instead of the client's own, which is left untouched.
+    ///
+    /// Unlike [set_remote](crate::Client::set_remote) this only borrows the client, so one client
+    /// can go through a whole server list, pinging any number of servers at once. The ping is sent
+    /// from this client's socket with its settings.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let client = Client::new("ip:port").await?;
+    /// let (first, second) = tokio::join!(
+    ///     client.raknet_ping_at("play.example.com:19132"),
+    ///     client.raknet_ping_at("mc.example.net:19132"),
+    /// );
+    /// println!("{} and {} players", first?.player_count, second?.player_count);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn raknet_ping_at<B: ToSocketAddrs>(&self, remote: B) -> Result<RakNetPong> {
+        self.at(remote).raknet_ping().await
+    }
+
+    /// Long queries the given remote instead of the client's own, see [raknet_ping_at](crate::Client::raknet_ping_at).
+    ///
+    /// The challenge token is never shared with the client's own (cached) one.
+    pub async fn long_query_at<B: ToSocketAddrs>(&self, remote: B) -> Result<LongQuery> {
+        self.at(remote).long_query().await
+    }
+
+    /// Short queries the given remote instead of the client's own, see [long_query_at](crate::Client::long_query_at).
+    pub async fn short_query_at<B: ToSocketAddrs>(&self, remote: B) -> Result<ShortQuery> {
+        self.at(remote).short_query().await
+    }
+
+    /// A client for another remote sharing this one's socket (and pcap writer) and settings, with its own token cache.
+    fn at<B: ToSocketAddrs>(&self, remote: B) -> Client<B> {
+        Client {
+            router: self.router.clone(),
+            remote,
+            identity: self.identity,
+            timeout: self.timeout,
+            retries: self.retries,
+            backoff: self.backoff,
+            jitter: self.jitter,
+            max_response: self.max_response,
+            player_limit: self.player_limit,
+            legacy: self.legacy,
+            pipeline: self.pipeline,
+            map_remote: self.map_remote.clone(),
+            token: std::sync::Mutex::new(None),
+            #[cfg(feature = "pcap")]
+            pcap: self.pcap.clone(),
+            #[cfg(feature = "socks5")]
+            proxy: self.proxy.clone(),
+        }
+    }
+
+    /// Forgets the cached challenge token, so the next query does a new handshake.
+    ///
+    /// Queries reuse a challenge token for [TOKEN_LIFETIME](crate::model::packet::TOKEN_LIFETIME)
+    /// after the handshake that got it, saving a round trip when polling a server on a tight interval.
+    /// Changing the remote or identity clears the cache as well.
+    pub fn clear_token_cache(&mut self) {
+        *self.token.get_mut().unwrap() = None;
+    }
+
+    /// Makes every query sent by this client attributable to the given label.
+    ///
+    /// Neither GS3 nor RakNet have a user-agent field, so instead the label is hashed and used as
+    /// the raknet client GUID and as the GS3 session id. Repeated queries with the same label
+    /// always send the same ids, which lets server admins recognise (and allowlist) your tool in their logs.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let mut client = Client::new("ip:port").await?;
+    /// client.identify_as("my-server-monitor");
+    /// // Both queries now carry ids derived from "my-server-monitor".
+    /// let pong = client.raknet_ping().await?;
+    /// let data = client.long_query().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn identify_as(&mut self, label: &str) {
+        self.identity = Some(utils::fnv1a(label.as_bytes()));
+        self.clear_token_cache();
+    }
+
+    /// Records every datagram this client sends and receives to the writer as a pcap capture.
+    ///
+    /// Only available with the `pcap` feature. The capture can be opened in Wireshark, which is
+    /// handy when reverse engineering what a server actually responds with.
+    ///
+    /// # [Errors]
+    /// - Failing to write the pcap header
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let mut client = Client::new("ip:port").await?;
+    /// client.set_pcap_writer(std::fs::File::create("query.pcap")?)?;
+    /// client.long_query().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "pcap")]
+    pub fn set_pcap_writer<W: std::io::Write + Send + 'static>(&mut self, writer: W) -> Result<()> {
+        self.pcap = Some(Arc::new(std::sync::Mutex::new(pcap::PcapWriter::new(writer)?)));
+        Ok(())
+    }
+
+    /// Sends a datagram to the resolved remote, returning when it was sent.
+    ///
+    /// The datagram is recorded to the capture before the router takes the time, so that doesn't
+    /// count towards a round-trip time measured from it.
+    async fn send(&self, remote: SocketAddr, buf: &[u8]) -> Result<Instant> {
+        #[cfg(feature = "pcap")]
+        if let Some(pcap) = &self.pcap {
+            pcap.lock().unwrap().record(self.router.local_addr()?, remote, buf)?;
+        }
+        #[cfg(feature = "socks5")]
+        let sent = match &self.proxy {
+            Some(relay) => self.router.send_to(&socks5::wrap(remote, buf), relay.addr).await?,
+            None => self.router.send_to(buf, remote).await?,
+        };
+        #[cfg(not(feature = "socks5"))]
+        let sent = self.router.send_to(buf, remote).await?;
+        trace_event!(debug, remote = %remote, bytes = buf.len(), "sent request");
+        Ok(sent)
+    }
+
+    /// Receives the reply routed to the query, returning it with when it arrived.
+    ///
+    /// Only datagrams from the remote address are routed to it, see [Router](router::Router).
+    /// The timeout covers the whole wait rather than each datagram.
+    async fn recv(&self, remote: SocketAddr, reply: &mut router::Expected) -> Result<(Vec<u8>, Instant)> {
+        self.recv_matching(remote, reply, |_| true).await
+    }
+
+    /// Receives the stat response for the session, discarding ones for other sessions.
+    ///
+    /// Those are late responses to earlier queries. Other packets are kept so parsing reports them.
+    /// Some servers (vanilla java ones) answer with the session of the handshake the token came
+    /// from instead of the stat request's, so the handshake's session is passed along too.
+    async fn recv_stat(&self, remote: SocketAddr, sessions: &[i32], reply: &mut router::Expected) -> Result<(Vec<u8>, Instant)> {
+        let sessions: Vec<_> = sessions.iter().map(|sid| (sid & packet::SESSION_ID_MASK).to_be_bytes()).collect();
+        self.recv_matching(remote, reply, |datagram| datagram.first() != Some(&packet::STAT)
+            || sessions.iter().any(|session| datagram.get(1..5) == Some(&session[..]))).await
+    }
+
+    /// Receives the first datagram routed to the query that is accepted, see [recv](Client::recv).
+    async fn recv_matching(&self, remote: SocketAddr, reply: &mut router::Expected, accept: impl Fn(&[u8]) -> bool) -> Result<(Vec<u8>, Instant)> {
+        match self.timeout {
+            Some(timeout) => time::timeout(timeout, self.recv_from(remote, reply, accept)).await.map_err(|_| Error::Timeout)?,
+            None => self.recv_from(remote, reply, accept).await,
+        }
+    }
+
+    /// Waits for datagrams routed to the query until an accepted one arrives.
+    #[cfg_attr(not(feature = "pcap"), allow(unused_variables))]
+    async fn recv_from(&self, remote: SocketAddr, reply: &mut router::Expected, accept: impl Fn(&[u8]) -> bool) -> Result<(Vec<u8>, Instant)> {
+        loop {
+            let (datagram, received) = reply.recv().await?;
+            // a spoofed or hostile datagram mustn't fail the query, the real reply may still come
+            if datagram.len() > self.max_response {
+                trace_event!(debug, remote = %remote, bytes = datagram.len(), "discarded response over the size limit");
+                reply.rearm();
+                continue;
+            }
+            if !accept(&datagram) {
+                trace_event!(debug, remote = %remote, bytes = datagram.len(), "discarded response to another query");
+                reply.rearm();
+                continue;
+            }
+            trace_event!(debug, remote = %remote, bytes = datagram.len(), "received response");
+            #[cfg(feature = "pcap")]
+            if let Some(pcap) = &self.pcap {
+                pcap.lock().unwrap().record(remote, self.router.local_addr()?, &datagram)?;
+            }
+            return Ok((datagram, received));
+        }
+    }
+
+    /// Runs the query again, up to the configured number of retries, for as long as it times out.
+    ///
+    /// Every attempt is a new query, so it gets a fresh session id and challenge token.
+    /// The wait between attempts starts at the configured backoff and doubles every retry,
+    /// with [jitter](crate::ClientBuilder::jitter) it's a random part of that.
+    async fn retrying<T, F, Fut>(&self, query: F) -> Result<T>
+    where F: Fn() -> Fut, Fut: Future<Output = Result<T>>
+    {
+        let mut backoff = self.backoff;
+        let mut attempts = 0;
+        loop {
+            match query().await {
+                Err(Error::Timeout) if attempts < self.retries => {
+                    trace_event!(debug, attempt = attempts + 1, backoff_ms = backoff.as_millis() as u64, "query timed out, retrying");
+                    // the server may have dropped the stat because the cached token expired early
+                    *self.token.lock().unwrap() = None;
+                    time::sleep(self.retry_delay(backoff)).await;
+                    backoff = backoff.saturating_mul(2);
+                    attempts += 1;
+                },
+                result => {
+                    #[cfg(feature = "tracing")]
+                    if let Err(e) = &result {
+                        tracing::warn!(error = %e, "query failed");
+                    }
+                    return result
+                },
+            }
+        }
+    }
+
+    /// Parses a reply, tracing when the parse starts and how it went along with the exchange's round-trip time.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn parse<T>(&self, kind: &'static str, rtt: Duration, parse: impl FnOnce() -> Result<T>) -> Result<T> {
+        trace_event!(trace, kind, "parse started");
+        let result = parse();
+        match &result {
+            Ok(_) => trace_event!(debug, kind, elapsed_ms = rtt.as_millis() as u64, "parse finished"),
+            Err(e) => trace_event!(debug, kind, elapsed_ms = rtt.as_millis() as u64, error = %e, "parse failed"),
+        }
+        result
+    }
+
+    /// How long to wait before retrying, anywhere up to the backoff with jitter ("full jitter").
+    fn retry_delay(&self, backoff: Duration) -> Duration {
+        if self.jitter {
+            backoff.mul_f64(rand::random::<f64>())
+        } else {
+            backoff
+        }
+    }
+
+    /// Returns a session id for the stat request, a challenge token and the session id the token was
+    /// handed out for, reusing the cached token while it's still valid.
+    ///
+    /// Tokens are bound to the client's address rather than the session, so every stat request gets
+    /// a session of its own (unless there's an identity) and late replies to earlier queries can be
+    /// told apart. A pinned session always gets a new token, which isn't cached.
+    async fn challenge(&self, session: Option<i32>) -> Result<(i32, i32, i32)> {
+        if let Some(sid) = session {
+            return Ok((sid, self.gen_challenge_token(sid).await?, sid));
+        }
+        if let Some((handshake, token, at)) = *self.token.lock().unwrap() {
+            if at.elapsed() < packet::TOKEN_LIFETIME {
+                return Ok((self.session_id(), token, handshake));
+            }
+        }
+        let handshake = self.session_id();
+        let at = Instant::now();
+        let token = self.gen_challenge_token(handshake).await?;
+        *self.token.lock().unwrap() = Some((handshake, token, at));
+        Ok((self.session_id(), token, handshake))
+    }
+
+    /// The raknet client GUID, derived from the identity if one is set otherwise random.
+    fn client_id(&self) -> u64 {
+        self.identity.unwrap_or_else(rand::random)
+    }
+
+    /// The GS3 session id, derived from the identity if one is set otherwise random.
+    fn session_id(&self) -> i32 {
+        match self.identity {
+            Some(id) => ((id >> 32) ^ id) as i32,
+            None => rand::random(),
+        }
     }
 
     /// A fast and easy query using raknet unconnected ping and pong.
@@ -109,53 +555,252 @@ impl<A: ToSocketAddrs> Client<A> {
     ///
     /// # [Example]
     /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
     /// // Open local binded port and query the given server address.
-    /// let data = Client::new("ip:port").await?.short_query().await?;
+    /// let data = Client::new("ip:port").await?.raknet_ping().await?;
     /// // Prints out the amount of players on that server at the time of querying.
     /// println!("player_count: {}", data.player_count); // EX: player_count: 5
+    /// # Ok(())
+    /// # }
     /// ```
     pub async fn raknet_ping(&self) -> Result<RakNetPong> {
-        // Writing
-        let mut random = rand::thread_rng();
-        let offline_msg_data = Vec::from_hex("00ffff00fefefefefdfdfdfd12345678").expect("Failed to read binary string!");
-        {
-            //Initalize Buf with 0x01 being the ID_UNCONNECTED_PING
-            let mut buf: Vec<u8> = vec![0x01];
-            //Write the current time stamp
-            buf.write_i64::<BigEndian>(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64)?;
-            //Hex literal for Offline Message Data ID
-            buf.extend(&offline_msg_data);
-            //Write a random client id
-            buf.write_u64::<BigEndian>(random.gen::<u64>())?;
-            //Send query to remote socket
-            self.socket.send_to(buf.as_slice(), &self.remote).await?;
-        }; //purge temporary buf out of scope
-        // begin reading
-        let mut buf = [0u8; u16::MAX as usize];
-        //Read data into temp buffer ^^
-        let len = self.socket.recv(&mut buf).await?;
-        //Split the data into a vector made of Strings
-        let data: Vec<String> = String::from_utf8_lossy(&buf[offline_msg_data.len()+19..=len])
-            .split(';').map(String::from).collect();
-        let mut gamemode = None;
-        let mut motd = vec![data[1].clone()];
-        if data.len() > 7 {
-            motd.push(data[7].clone());
-            gamemode = Some(data[8].clone())
+        Ok(self.raknet_ping_timed().await?.0)
+    }
+
+    /// A raknet ping that also returns the round-trip time between sending the ping and receiving the pong.
+    ///
+    /// The time is measured around the socket only, so neither resolving the remote nor parsing the pong counts towards it.
+    ///
+    /// # [Errors]
+    /// - Same as [raknet_ping](crate::Client::raknet_ping)
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let (data, rtt) = Client::new("ip:port").await?.raknet_ping_timed().await?;
+    /// println!("{} players, pinged in {}", data.player_count, rsquery::fmt_latency(&rtt));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn raknet_ping_timed(&self) -> Result<(RakNetPong, Duration)> {
+        self.retrying(|| self.ping(None)).await
+    }
+
+    /// A raknet ping sending the given client id instead of a generated one.
+    ///
+    /// Some servers rate limit or track pings by client id, pinning it keeps every ping
+    /// looking like the same client. [identify_as](crate::Client::identify_as) pins it for every ping instead.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let data = Client::new("ip:port").await?.raknet_ping_with_client_id(0xDEADBEEF).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn raknet_ping_with_client_id(&self, id: u64) -> Result<RakNetPong> {
+        Ok(self.retrying(|| self.ping(Some(id))).await?.0)
+    }
+
+    /// A raknet ping returning the pong exactly as the server sent it, without parsing it.
+    ///
+    /// Meant for bug reports against servers whose pongs fail to parse, the bytes can be
+    /// attached as is or fed to [parse_pong](crate::parse::parse_pong) later on.
+    ///
+    /// # [Errors]
+    /// - Polling for timeout
+    /// - Connection Failure
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let pong = Client::new("ip:port").await?.raknet_ping_raw().await?;
+    /// println!("{}", hex::encode(&pong));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn raknet_ping_raw(&self) -> Result<Vec<u8>> {
+        Ok(self.retrying(|| self.ping_exchange(None)).await?.0)
+    }
+
+    /// Does a single ping and pong exchange.
+    async fn ping(&self, client_id: Option<u64>) -> Result<(RakNetPong, Duration)> {
+        let (reply, remote, rtt) = self.ping_exchange(client_id).await?;
+        let mut pong = self.parse("pong", rtt, || parse::parse_pong(&reply))?;
+        pong.remote = Some(remote);
+        Ok((pong, rtt))
+    }
+
+    /// Resolves the remote for a query, passing it through the [remote map](crate::ClientBuilder::map_remote) if there's one.
+    async fn resolve_remote(&self) -> Result<SocketAddr> {
+        let remote = utils::resolve(&self.remote).await?;
+        match &self.map_remote {
+            Some(map) => map.apply(remote),
+            None => Ok(remote),
+        }
+    }
+
+    /// Sends a ping and returns the unparsed pong, where it came from and the round-trip time.
+    async fn ping_exchange(&self, client_id: Option<u64>) -> Result<(Vec<u8>, SocketAddr, Duration)> {
+        let request = encode::ping(self.router.ping_time(), client_id.unwrap_or_else(|| self.client_id()));
+        let remote = self.resolve_remote().await?;
+        // the pong echoes the ping's timestamp
+        let mut reply = self.router.expect(remote, packet::UNCONNECTED_PONG, &request[1..9]);
+        //Send query to remote socket
+        let start = self.send(remote, &request).await?;
+        let (reply, received) = self.recv(remote, &mut reply).await?;
+        Ok((reply, remote, received - start))
+    }
+
+    /// Returns the online and max player counts, the cheapest way of checking a server is alive.
+    ///
+    /// This is a [raknet_ping](crate::Client::raknet_ping), so it needs a single round trip and
+    /// works on every bedrock server, even ones with query disabled.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let (online, max) = Client::new("ip:port").await?.player_count().await?;
+    /// println!("{}/{}", online, max);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn player_count(&self) -> Result<(usize, usize)> {
+        let pong = self.raknet_ping().await?;
+        Ok((pong.player_count, pong.max_player_count))
+    }
+
+    /// Returns whether the server answers a raknet ping with a pong, the simplest health check.
+    ///
+    /// Any pong counts, its server id string isn't parsed. Every failure (a timeout, an unreachable
+    /// remote or a reply that isn't a pong) is false, so set a [timeout](crate::ClientBuilder::timeout)
+    /// or a server that never answers keeps this waiting forever. Retries apply as usual.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::ClientBuilder;
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let client = ClientBuilder::new().timeout(Duration::from_secs(2)).build("ip:port").await?;
+    /// println!("{}", if client.is_online().await { "up" } else { "down" });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn is_online(&self) -> bool {
+        let pong = self.retrying(|| self.ping_exchange(None)).await.and_then(|(reply, _, _)| {
+            match reply.first() {
+                Some(&packet::UNCONNECTED_PONG) if utils::slice_index(&reply, &packet::OFFLINE_MESSAGE_DATA_ID).is_some() => Ok(()),
+                _ => Err(Error::MalformedResponse("Reply isn't an unconnected pong".to_string())),
+            }
+        });
+        matches!(model::QueryOutcome::from_result(pong), Ok(model::QueryOutcome::Online(())))
+    }
+
+    /// Raknet pings the remote, telling an offline server apart from one that answered with something wrong.
+    ///
+    /// Timeouts and unreachable remotes are [Offline](crate::model::QueryOutcome::Offline) instead of an
+    /// error, see [QueryOutcome](crate::model::QueryOutcome). Like [is_online](crate::Client::is_online)
+    /// this needs a [timeout](crate::ClientBuilder::timeout) to ever be offline.
+    ///
+    /// # [Errors]
+    /// - Invalid Data
+    /// - Connection Failure other than an unreachable remote
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::ClientBuilder;
+    /// # use rsquery::model::QueryOutcome;
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let client = ClientBuilder::new().timeout(Duration::from_secs(2)).build("ip:port").await?;
+    /// match client.raknet_ping_outcome().await? {
+    ///     QueryOutcome::Online(pong) => println!("{} players", pong.player_count),
+    ///     QueryOutcome::Offline => println!("offline"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn raknet_ping_outcome(&self) -> Result<model::QueryOutcome<RakNetPong>> {
+        model::QueryOutcome::from_result(self.raknet_ping().await)
+    }
+
+    /// Raknet pings the remote on an interval, yielding every result.
+    ///
+    /// Failed pings (e.g. timeouts, see [ClientBuilder::timeout](crate::ClientBuilder::timeout))
+    /// are yielded as Err variants instead of ending the stream, which never ends on its own.
+    /// A ping that takes longer than the interval delays the next one rather than overlapping it.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # use std::time::Duration;
+    /// use tokio_stream::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let client = Client::new("ip:port").await?;
+    /// let mut pings = Box::pin(client.ping_stream(Duration::from_secs(5)));
+    /// while let Some(data) = pings.next().await {
+    ///     match data {
+    ///         Ok(data) => println!("{} players", data.player_count),
+    ///         Err(e) => println!("offline: {}", e),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ping_stream(&self, interval: Duration) -> impl Stream<Item = Result<RakNetPong>> + '_ {
+        let mut ticker = time::interval(interval);
+        // the missed ticks would be yielded at once after a slow ping, bursting pings at the server
+        ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        IntervalStream::new(ticker).then(move |_| self.raknet_ping())
+    }
+
+    /// Raknet pings the remote on an interval, sending every result into the given channel.
+    ///
+    /// Failed pings are sent as Err variants, so the stream of results keeps going.
+    /// Returns once the receiving half of the channel is closed.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let client = Client::new("ip:port").await?;
+    /// let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    /// tokio::spawn(async move { client.stream_into(tx, Duration::from_secs(5)).await });
+    /// while let Some(data) = rx.recv().await {
+    ///     println!("{:?}", data);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn stream_into(&self, tx: mpsc::Sender<Result<RakNetPong>>, interval: Duration) {
+        let mut ticker = time::interval(interval);
+        // a ping slower than the interval pushes the next one back instead of firing the missed ticks at once
+        ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let data = tokio::select! {
+                data = self.raknet_ping() => data,
+                _ = tx.closed() => return,
+            };
+            if tx.send(data).await.is_err() {
+                return;
+            }
         }
-        Ok(RakNetPong {
-            game_edition: data[0].clone(),
-            motd,
-            protocol_version: data[2].parse().unwrap(),
-            game_version: data[3].clone(),
-            player_count: data[4].parse().unwrap(),
-            max_player_count: data[5].parse().unwrap(),
-            server_uid: data[6].clone(),
-            game_mode: gamemode,
-            game_mode_integer: None,
-            port: None,
-            port_v6: None
-        })
     }
 
     /// A slightly slower query implementation, but returns more detailed data.
@@ -173,89 +818,151 @@ impl<A: ToSocketAddrs> Client<A> {
     ///
     /// # [Example]
     /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
     /// // Open local binded port and long query the given server address
     /// let data = Client::new("ip:port").await?.long_query().await?;
     /// // Prints out the Vec<String> using Debug trait.
-    /// println!("players: {:?}", data.players) // EX: players: ["Timmy", "Bobby2454"]
+    /// println!("players: {:?}", data.players); // EX: players: ["Timmy", "Bobby2454"]
+    /// # Ok(())
+    /// # }
     /// ```
     pub async fn long_query(&self) -> Result<LongQuery> {
-        let mut random = rand::thread_rng();
-        let ses_id: i32 = random.gen();
-        let challenge_token = self.gen_challenge_token(ses_id).await?;
-        //Send Request
-        {
-            let mut buf: Vec<u8> = Vec::new();
-            // Write Query Magic
-            buf.write_u16::<BigEndian>(packet::MAGIC)?;
-            // Write STAT for the packet id
-            buf.write_u8(packet::STAT)?;
-            // Write Session Id
-            buf.write_i32::<BigEndian>(ses_id & 0x0F0F0F0F)?;
-            // Write challenge token
-            buf.write_i32::<BigEndian>(challenge_token)?;
-            // Padding
-            buf.write_all([0x00].repeat(4).as_slice())?;
-            // Send STAT request to remote
-            self.socket.send_to(buf.as_slice(), &self.remote).await?;
+        self.long_query_require(&[]).await
+    }
+
+    /// A long query that also returns the round-trip time of the stat request.
+    ///
+    /// Only the stat request is timed, the handshake before it is its own round trip,
+    /// see [gen_challenge_token_timed](crate::Client::gen_challenge_token_timed).
+    ///
+    /// # [Errors]
+    /// - Same as [long_query](crate::Client::long_query)
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let (data, rtt) = Client::new("ip:port").await?.long_query_timed().await?;
+    /// println!("{:?} answered in {}", data.players, rsquery::fmt_latency(&rtt));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn long_query_timed(&self) -> Result<(LongQuery, Duration)> {
+        self.retrying(|| self.full_stat(&[], None)).await
+    }
+
+    /// A long query that fails unless the server sent every one of the given keys.
+    ///
+    /// Useful for servers you manage that must always expose certain (custom) keys,
+    /// the error names exactly which of the required keys were missing.
+    ///
+    /// # [Errors]
+    /// - Same as [long_query](crate::Client::long_query)
+    /// - Malformed Response if any of the keys is missing
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// // Errors with Error::MalformedResponse naming tps if the server has no tps key
+    /// let data = Client::new("ip:port").await?.long_query_require(&["tps", "version"]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn long_query_require(&self, keys: &[&str]) -> Result<LongQuery> {
+        Ok(self.retrying(|| self.full_stat(keys, None)).await?.0)
+    }
+
+    /// A long query using the given session id instead of a generated one.
+    ///
+    /// Meant for diagnostics, a pinned session id makes the query easy to pick out of a packet
+    /// capture. The id is masked with `0x0F0F0F0F` like every session id, and always does a new handshake.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// // Shows up as session 0x01020304 in Wireshark
+    /// let data = Client::new("ip:port").await?.long_query_with_session(0x01020304).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn long_query_with_session(&self, ses_id: i32) -> Result<LongQuery> {
+        Ok(self.retrying(|| self.full_stat(&[], Some(ses_id))).await?.0)
+    }
+
+    /// A long query returning the full stat response exactly as the server sent it, without parsing it.
+    ///
+    /// See [raknet_ping_raw](crate::Client::raknet_ping_raw), the bytes can be parsed later on
+    /// with [parse_full_stat](crate::parse::parse_full_stat).
+    ///
+    /// # [Errors]
+    /// - Polling for timeout
+    /// - Invalid handshake reply
+    /// - Connection Failure
+    pub async fn long_query_raw(&self) -> Result<Vec<u8>> {
+        Ok(self.retrying(|| self.stat_exchange(None, true)).await?.0)
+    }
+
+    /// Does the full stat exchange, returning the long query and the round-trip time of the stat request.
+    async fn full_stat(&self, keys: &[&str], session: Option<i32>) -> Result<(LongQuery, Duration)> {
+        let (reply, rtt) = self.stat_exchange(session, true).await?;
+        Ok((self.parse("full stat", rtt, || parse::parse_full_stat_requiring(&reply, keys, self.player_limit))?, rtt))
+    }
+
+    /// Does the handshake and sends a stat request, returning the unparsed reply and the round-trip
+    /// time of the stat request.
+    async fn stat_exchange(&self, session: Option<i32>, full: bool) -> Result<(Vec<u8>, Duration)> {
+        if self.pipeline && !self.legacy && session.is_none() {
+            let expired = (*self.token.lock().unwrap()).filter(|(_, _, at)| at.elapsed() >= packet::TOKEN_LIFETIME);
+            if let Some((handshake, token, _)) = expired {
+                return self.pipelined_stat(handshake, token, full).await;
+            }
+        }
+        let (ses_id, handshake, request) = if self.legacy {
+            let ses_id = session.unwrap_or_else(|| self.session_id());
+            (ses_id, ses_id, encode::legacy_stat(ses_id, full))
+        } else {
+            let (ses_id, challenge_token, handshake) = self.challenge(session).await?;
+            (ses_id, handshake, encode::stat(ses_id, challenge_token, full))
         };
+        let remote = self.resolve_remote().await?;
+        // registering the reply routes one to a cancelled query that's still queued away first
+        let mut reply = self.router.expect(remote, packet::STAT, &request[3..7]);
+        // Send STAT request to remote
+        let start = self.send(remote, &request).await?;
         //Reading
-        let mut buf = [0u8; u16::MAX as usize];
-        let len = self.socket.recv(&mut buf).await?;
-        //check if the packet id is STAT
-        match buf[0] {
-            packet::STAT => {
-                let data = &buf[16..=len];
-                let mut reg_data = &buf[16..=len];
-                let players: Mutex<Vec<String>> = Mutex::new(Vec::new());
-                let raw_data: Mutex<HashMap<&str, String>> = Mutex::new(HashMap::new());
-                let player_index = utils::slice_index(data, &packet::PLAYER_KEY);
-                if let Some(pi) = player_index {
-                    reg_data = &data[0..=pi];
-                };
-                let a = async || -> Result<()> {
-                    let mut arr = reg_data.split(|byte| byte == &0x00u8).collect::<Vec<&[u8]>>();
-                    if arr.len() % 2 != 0 {
-                        arr.pop();
-                    }
-                    let mut i: usize = 1;
-                    for k in arr.iter().step_by(2) {
-                        raw_data
-                            .lock().await
-                            .insert(str::from_utf8(*k).expect("Unable to decode key string"),
-                                    str::from_utf8(arr[i]).expect("Unable to decode value string").to_string());
-                        i += 2;
-                    }
-                    Ok(())
-                };
-                let b = async || -> Result<()> {
-                    if let Some(pi) = player_index {
-                        let tmp = &data[pi+packet::PLAYER_KEY.len()..data.len()-3];
-                        players.lock().await.extend(tmp.split(|byte| byte == &0x00u8)
-                            .map(|arr| str::from_utf8(arr).expect("Failure decoding string!").to_string()));
-                    };
-                    Ok(())
-                };
-                tokio::try_join!(a(), b())?;
-                let reader = raw_data.lock().await;
-                let players = players.lock().await.to_vec();
-                Ok(LongQuery {
-                    server_software: reader.get("server_engine").expect("Failed to find server_engine").clone(),
-                    plugins: reader.get("plugins").expect("Failed to find plugins").clone(),
-                    version: reader.get("version").expect("Failed to find version").clone(),
-                    whitelist: reader.get("whitelist").expect("Failed to find whitelist").clone(),
-                    players,
-                    player_count: reader.get("numplayers").expect("Failed to find numplayers").parse().expect("Invalid Player Count!"),
-                    max_players: reader.get("maxplayers").expect("Failed to find maxplayers").parse().expect("Invalid Max Player Count!"),
-                    game_name: reader.get("game_id").expect("Failed to find gamename").clone(),
-                    game_mode: reader.get("gametype").expect("Failed to find gametype").clone(),
-                    map_name: reader.get("map").expect("Failed to find map").clone(),
-                    host_name: reader.get("hostname").expect("Failed to find server_engine").clone(),
-                    host_ip: reader.get("hostip").expect("Failed to find hostip").clone(),
-                    host_port: reader.get("hostport").expect("Failed to find server_engine").parse().expect("Invalid Host Port!")
-                })
-            },
-            _ => Err(Error::new(ErrorKind::InvalidData, "Unexpected packet was received while awaiting 0x00 STAT"))
-        }
+        let (reply, received) = self.recv_stat(remote, &[ses_id, handshake], &mut reply).await?;
+        Ok((reply, received - start))
+    }
+
+    /// Raknet pings then long queries the remote, merging both into a [ServerProfile](crate::model::ServerProfile).
+    ///
+    /// Both run on the same socket one after the other, so this costs the round trips of a
+    /// [raknet_ping](crate::Client::raknet_ping) and a [long_query](crate::Client::long_query) together.
+    ///
+    /// # [Errors]
+    /// - Same as [raknet_ping](crate::Client::raknet_ping) and [long_query](crate::Client::long_query)
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let profile = Client::new("ip:port").await?.full_profile().await?;
+    /// println!("{} running {} on port {}", profile.host_name, profile.server_software, profile.port);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn full_profile(&self) -> Result<ServerProfile> {
+        let pong = self.raknet_ping().await?;
+        let query = self.long_query().await?;
+        Ok(ServerProfile::new(pong, query))
     }
 
     /// A slightly faster implementation of the long query found in BASIC STAT for GS3
@@ -273,82 +980,268 @@ impl<A: ToSocketAddrs> Client<A> {
     ///
     /// # [Example]
     /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
     /// // Open local binded port and long query the given server address
     /// let data = Client::new("ip:port").await?.short_query().await?;
     /// // Prints out the usize using Display trait.
-    /// println!("players: {}", data.players) // EX: players: 2
+    /// println!("players: {}", data.players); // EX: players: 2
+    /// # Ok(())
+    /// # }
     /// ```
     pub async fn short_query(&self) -> Result<ShortQuery> {
-        let mut random = rand::thread_rng();
-        let ses_id: i32 = random.gen();
-        let challenge_token = self.gen_challenge_token(ses_id).await?;
-        {
-            let mut buf: Vec<u8> = Vec::new();
-            // Write Query Magic
-            buf.write_u16::<BigEndian>(packet::MAGIC)?;
-            // Write STAT for the packet id
-            buf.write_u8(packet::STAT)?;
-            // Write Session Id
-            buf.write_i32::<BigEndian>(ses_id & 0x0F0F0F0F)?;
-            // Write challenge token
-            buf.write_i32::<BigEndian>(challenge_token)?;
-            // Send STAT request to remote
-            self.socket.send_to(buf.as_slice(), &self.remote).await?;
+        Ok(self.short_query_timed().await?.0)
+    }
+
+    /// A short query that also returns the round-trip time of the stat request.
+    ///
+    /// Only the stat request is timed, the handshake before it is its own round trip,
+    /// see [gen_challenge_token_timed](crate::Client::gen_challenge_token_timed).
+    ///
+    /// # [Errors]
+    /// - Same as [short_query](crate::Client::short_query)
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let (data, rtt) = Client::new("ip:port").await?.short_query_timed().await?;
+    /// println!("{} players, answered in {}", data.players, rsquery::fmt_latency(&rtt));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn short_query_timed(&self) -> Result<(ShortQuery, Duration)> {
+        self.retrying(|| self.basic_stat(None)).await
+    }
+
+    /// A short query using the given session id instead of a generated one.
+    ///
+    /// See [long_query_with_session](crate::Client::long_query_with_session).
+    pub async fn short_query_with_session(&self, ses_id: i32) -> Result<ShortQuery> {
+        Ok(self.retrying(|| self.basic_stat(Some(ses_id))).await?.0)
+    }
+
+    /// A short query returning the basic stat response exactly as the server sent it, without parsing it.
+    ///
+    /// See [long_query_raw](crate::Client::long_query_raw), the bytes can be parsed later on
+    /// with [parse_short_stat](crate::parse::parse_short_stat).
+    pub async fn short_query_raw(&self) -> Result<Vec<u8>> {
+        Ok(self.retrying(|| self.stat_exchange(None, false)).await?.0)
+    }
+
+    /// Sends a stat request with the expired token while fetching a new one, returning the first reply
+    /// to either stat request, see [ClientBuilder::pipeline](crate::ClientBuilder::pipeline).
+    ///
+    /// Both stat requests have the same session, so the round-trip time is measured from the last one
+    /// sent before the reply arrived.
+    async fn pipelined_stat(&self, expired_handshake: i32, expired_token: i32, full: bool) -> Result<(Vec<u8>, Duration)> {
+        let (ses_id, handshake) = (self.session_id(), self.session_id());
+        let request = encode::stat(ses_id, expired_token, full);
+        let remote = self.resolve_remote().await?;
+        let mut reply = self.router.expect(remote, packet::STAT, &request[3..7]);
+        let start = self.send(remote, &request).await?;
+        let renew = async {
+            let at = Instant::now();
+            let token = self.gen_challenge_token(handshake).await?;
+            *self.token.lock().unwrap() = Some((handshake, token, at));
+            trace_event!(debug, remote = %remote, "resending the stat request with a new token");
+            self.send(remote, &encode::stat(ses_id, token, full)).await
         };
-        //Reading
-        let mut buf = [0u8; u16::MAX as usize];
-        let len = self.socket.recv(&mut buf).await?;
-        match buf[0] {
-            packet::STAT => {
-                let mut buf = Cursor::new(&buf[5..len]);
-                let motd = read_nulltermed_str(&mut buf).await?;
-                let gametype = read_nulltermed_str(&mut buf).await?;
-                let map = read_nulltermed_str(&mut buf).await?;
-                let players = read_nulltermed_str(&mut buf).await?.parse().unwrap();
-                let max_players = read_nulltermed_str(&mut buf).await?.parse().unwrap();
-                let host_port = buf.read_u16::<LittleEndian>()?;
-                let host_ip = read_nulltermed_str(&mut buf).await?;
-                Ok(ShortQuery {
-                    motd,
-                    gametype,
-                    map,
-                    players,
-                    max_players,
-                    host_port,
-                    host_ip
-                })
-            },
-            _ => Err(Error::new(ErrorKind::InvalidData, "Unexpected packet was received while awaiting 0x00 STAT")),
+        let sessions = [ses_id, expired_handshake, handshake];
+        let received = self.recv_stat(remote, &sessions, &mut reply);
+        tokio::pin!(received);
+        let resent = tokio::select! {
+            received = &mut received => return received.map(|(reply, received)| (reply, received - start)),
+            resent = renew => resent?,
+        };
+        let (reply, received) = received.await?;
+        Ok((reply, received.saturating_duration_since(resent)))
+    }
+
+    /// Does the basic stat exchange, returning the short query and the round-trip time of the stat request.
+    async fn basic_stat(&self, session: Option<i32>) -> Result<(ShortQuery, Duration)> {
+        let (reply, rtt) = self.stat_exchange(session, false).await?;
+        Ok((self.parse("basic stat", rtt, || parse::parse_short_stat(&reply))?, rtt))
+    }
+
+    /// Queries a java edition server with the server list ping.
+    ///
+    /// Only available with the `java` feature. The ping runs over TCP, so the remote has to be
+    /// the server's java port (25565 by default) and the client's udp socket isn't used.
+    /// The configured timeout applies to the whole exchange. The handshake names the remote's host
+    /// as the client was given it (see [Host](crate::Host)), like the game does.
+    ///
+    /// For information on the data returned view [JavaStatus](crate::model::JavaStatus)
+    ///
+    /// # [Errors]
+    /// - Connection Failure
+    /// - Malformed Response if the status isn't valid json
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let status = Client::new("ip:25565").await?.java_status().await?;
+    /// println!("{} ({}/{})", status.description, status.players.online, status.players.max);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "java")]
+    pub async fn java_status(&self) -> Result<model::JavaStatus>
+    where A: Host
+    {
+        let remote = self.resolve_remote().await?;
+        let status = async {
+            let mut stream = tokio::net::TcpStream::connect(remote).await?;
+            java::status(&mut stream, &self.remote.host(), remote.port()).await
+        };
+        match self.timeout {
+            Some(timeout) => time::timeout(timeout, status).await.map_err(|_| Error::Timeout)?,
+            None => status.await,
         }
     }
 
     /// Generates a challenge token for a given session id
+    ///
+    /// The session id is masked with [SESSION_ID_MASK](crate::model::packet::SESSION_ID_MASK)
+    /// (`0x0F0F0F0F`) before it's sent, servers answer and validate the masked id. The handshake
+    /// request is 7 bytes, all big endian:
+    ///
+    /// | bytes | value |
+    /// |-------|-------|
+    /// | 0..2  | [MAGIC](crate::model::packet::MAGIC) `0xFEFD` |
+    /// | 2     | [HANDSHAKE](crate::model::packet::HANDSHAKE) `0x09` |
+    /// | 3..7  | masked session id |
+    ///
+    /// The server replies with `0x09`, the masked session id and the token as a null terminated
+    /// decimal string, which is parsed into the returned i32. A STAT request has to carry the
+    /// same masked session id followed by the token (as 4 big endian bytes), see
+    /// [gen_challenge_token_masked](crate::Client::gen_challenge_token_masked) for getting both.
+    /// Tokens expire after [TOKEN_LIFETIME](crate::model::packet::TOKEN_LIFETIME).
+    ///
     /// # [Example]
     /// with a random session id
     ///
     /// ```no_run
+    /// # use rsquery::Client;
+    /// # use rand::Rng;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
     /// let token: i32 = Client::new("ip:port").await?.gen_challenge_token(rand::thread_rng().gen()).await?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub async fn gen_challenge_token(&self, sid: i32) -> Result<i32> {
-        let mut buf: Vec<u8> = Vec::new();
-        //Writes query protocol magic to the buf always 0xFEFD
-        buf.write_u16::<BigEndian>(packet::MAGIC)?;
-        //Sending a handshake so the server sends back a challenge token for our given session id (always 0x09)
-        buf.write_u8(packet::HANDSHAKE)?;
-        //Writing the sid to the buf
-        buf.write_i32::<BigEndian>(sid & 0x0F0F0F0F)?;
+        Ok(self.gen_challenge_token_raw(sid).await?.0)
+    }
+
+    /// Generates a challenge token for a given session id, returning it with the masked session id.
+    ///
+    /// These are the two values a STAT request needs after the magic and `0x00` id, in this order,
+    /// see [gen_challenge_token](crate::Client::gen_challenge_token) for the wire format.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let (session, token) = Client::new("ip:port").await?.gen_challenge_token_masked(0x7172_7374).await?;
+    /// // A basic stat request, a full stat request appends 4 bytes of padding
+    /// let mut stat = vec![0xFE, 0xFD, 0x00];
+    /// stat.extend_from_slice(&session.to_be_bytes());
+    /// stat.extend_from_slice(&token.to_be_bytes());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn gen_challenge_token_masked(&self, sid: i32) -> Result<(i32, i32)> {
+        Ok((sid & packet::SESSION_ID_MASK, self.gen_challenge_token(sid).await?))
+    }
+
+    /// Generates a challenge token for a given session id, also returning the raw handshake reply.
+    ///
+    /// Meant for debugging servers with unusual handshake replies, when the token can't
+    /// be parsed the returned error contains the reply as hex.
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let (token, reply) = Client::new("ip:port").await?.gen_challenge_token_raw(1).await?;
+    /// println!("token {} from {:?}", token, reply);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn gen_challenge_token_raw(&self, sid: i32) -> Result<(i32, Vec<u8>)> {
+        let (token, reply, _) = self.handshake(sid).await?;
+        Ok((token, reply))
+    }
+
+    /// Generates a challenge token for a given session id, also returning the handshake round-trip time.
+    ///
+    /// The handshake is its own round trip apart from the stat request, so timing it alone
+    /// helps spotting servers that are slow to hand out tokens (often a busy query thread).
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let (token, rtt) = Client::new("ip:port").await?.gen_challenge_token_timed(1).await?;
+    /// println!("handshake took {}", rsquery::fmt_latency(&rtt));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn gen_challenge_token_timed(&self, sid: i32) -> Result<(i32, Duration)> {
+        let (token, _, rtt) = self.handshake(sid).await?;
+        Ok((token, rtt))
+    }
+
+    /// Does the handshake round trip, returning the token, the raw reply and the round-trip time.
+    async fn handshake(&self, sid: i32) -> Result<(i32, Vec<u8>, Duration)> {
+        let request = encode::handshake(sid);
+        let remote = self.resolve_remote().await?;
+        let mut reply = self.router.expect(remote, packet::HANDSHAKE, &request[3..7]);
         //Use locally bound port to send to remote.
-        self.socket.send_to(buf.as_slice(), &self.remote).await?;
-        //remove buf from mem
-        drop(buf);
+        let start = self.send(remote, &request).await?;
         //Begin reading the data
-        let mut buf = [0u8; (u16::MAX >> 2) as usize];
-        let len = self.socket.recv(&mut buf).await?;
-        match buf[0] {
-            packet::HANDSHAKE => {
-                Ok(String::from_utf8_lossy(&buf[5..len-1]).parse().expect("Invalid Challenge Token Received"))
-            },
-            _ => Err(Error::new(ErrorKind::InvalidData, "Wrong packet received perhaps an already opened session? (expected 0x01 Handshake)"))
-        }
+        let (reply, received) = self.recv(remote, &mut reply).await?;
+        let rtt = received - start;
+        Ok((self.parse("handshake", rtt, || parse::parse_challenge_token(&reply))?, reply, rtt))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Client<SocketAddr> {
+    /// Constructs a new Client targeted to the first address the remote resolves to.
+    ///
+    /// The remote is only resolved once, so repeated queries skip DNS and every packet
+    /// goes to the same address even when a hostname has round-robin DNS.
+    ///
+    /// # [Errors]
+    /// - On bind failure
+    /// - If the remote doesn't resolve to any address
+    ///
+    /// # [Example]
+    /// ```no_run
+    /// # use rsquery::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> rsquery::Result<()> {
+    /// let client = Client::resolve("play.example.com:19132").await?;
+    /// println!("pinging {}", client.resolved_addr());
+    /// let data = client.raknet_ping().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn resolve<R: ToSocketAddrs>(remote: R) -> Result<Self> {
+        ClientBuilder::new().resolve(remote).await
+    }
+
+    /// Returns the address every query of this client goes to.
+    pub fn resolved_addr(&self) -> SocketAddr {
+        self.remote
     }
 }
@@ -0,0 +1,27 @@
+use core::net::{IpAddr, SocketAddr};
+
+/// AddrMismatch describes how a server's self-reported host address differs from the queried one
+///
+/// A mismatch usually means the server is behind a proxy or reports an internal/localhost address.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddrMismatch {
+    Ip,
+    Port,
+    Both
+}
+
+impl AddrMismatch {
+    /// Compares a reported host ip and port against the queried address.
+    ///
+    /// A host ip that isn't an ip literal (e.g. a hostname) counts as a different ip.
+    pub(crate) fn between(host_ip: &str, host_port: u16, queried: SocketAddr) -> Option<Self> {
+        let ip_differs = host_ip.parse::<IpAddr>().map_or(true, |ip| ip != queried.ip());
+        match (ip_differs, host_port != queried.port()) {
+            (false, false) => None,
+            (true, false) => Some(AddrMismatch::Ip),
+            (false, true) => Some(AddrMismatch::Port),
+            (true, true) => Some(AddrMismatch::Both),
+        }
+    }
+}
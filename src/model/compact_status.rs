@@ -0,0 +1,23 @@
+use core::convert::TryFrom;
+
+/// CompactStatus is a tiny fixed-size summary of any query result
+///
+/// It doesn't allocate, which makes it suitable for constrained devices
+/// that can't hold the full model. Counts that don't fit a u16 saturate at u16::MAX.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactStatus {
+    pub online: bool,
+    pub players: u16,
+    pub max: u16
+}
+
+impl CompactStatus {
+    pub(crate) fn new(players: usize, max: usize) -> Self {
+        CompactStatus {
+            online: true,
+            players: u16::try_from(players).unwrap_or(u16::MAX),
+            max: u16::try_from(max).unwrap_or(u16::MAX),
+        }
+    }
+}
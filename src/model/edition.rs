@@ -0,0 +1,28 @@
+/// The Minecraft edition a server is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edition {
+    Bedrock,
+    Education,
+    Java,
+    /// The server sent an edition this crate doesn't know.
+    Unknown
+}
+
+impl Edition {
+    /// Maps the edition id of a raknet pong (`MCPE` or `MCEE`) to its edition.
+    pub(crate) fn from_raknet(edition: &str) -> Self {
+        match edition {
+            "MCPE" => Edition::Bedrock,
+            "MCEE" => Edition::Education,
+            _ => Edition::Unknown
+        }
+    }
+
+    /// None for [Unknown](Edition::Unknown), which is what [QueryData](crate::model::QueryData) reports.
+    pub(crate) fn known(self) -> Option<Self> {
+        match self {
+            Edition::Unknown => None,
+            edition => Some(edition)
+        }
+    }
+}
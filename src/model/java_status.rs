@@ -0,0 +1,98 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// The 8 bytes every png starts with.
+const PNG_SIGNATURE: [u8; 8] = *b"\x89PNG\r\n\x1a\n";
+
+/// JavaStatus is a model of the status json returned by a java edition server list ping
+///
+/// Only available with the `java` feature. This is what the multiplayer screen shows:
+/// the version, the player counts with a sample of names, the motd and the server icon.
+///
+#[derive(Debug, Clone, Deserialize)]
+pub struct JavaStatus {
+    pub version: JavaVersion,
+    pub players: JavaPlayers,
+    /// The motd as plain text, chat component motds are flattened but keep their `§` codes.
+    #[serde(default, deserialize_with = "component_text")]
+    pub description: String,
+    /// The server icon as a `data:image/png;base64,` uri, None if the server has none.
+    #[serde(default)]
+    pub favicon: Option<String>,
+}
+
+impl JavaStatus {
+    /// Decodes the favicon to the bytes of the PNG image.
+    ///
+    /// Returns None if the server sent no favicon or it isn't a base64 png data uri.
+    /// Line breaks inside the base64, which some older servers send, are ignored.
+    pub fn favicon_png(&self) -> Option<Vec<u8>> {
+        let data = self.favicon.as_ref()?.strip_prefix("data:image/png;base64,")?;
+        let data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+        STANDARD.decode(data).ok()
+    }
+
+    /// Returns the favicon's width and height in pixels, the game expects 64x64.
+    ///
+    /// Only the start of the image, its signature and IHDR chunk, is decoded to read them. Returns
+    /// None if the server sent no favicon, it isn't a base64 png data uri or it's too short to be a png.
+    pub fn favicon_dimensions(&self) -> Option<(u32, u32)> {
+        let data = self.favicon.as_ref()?.strip_prefix("data:image/png;base64,")?;
+        // 32 base64 characters are the first 24 bytes: the signature, the IHDR length and type, width and height
+        let data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).take(32).collect();
+        let header = STANDARD.decode(data).ok()?;
+        if header.len() < 24 || header[..8] != PNG_SIGNATURE || &header[12..16] != b"IHDR" {
+            return None;
+        }
+        let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]);
+        let height = u32::from_be_bytes([header[20], header[21], header[22], header[23]]);
+        Some((width, height))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JavaVersion {
+    /// The version name, which servers (especially proxies) often replace with their own text.
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JavaPlayers {
+    pub online: usize,
+    pub max: usize,
+    /// Some of the online players, servers send at most a dozen or none at all.
+    #[serde(default)]
+    pub sample: Vec<JavaPlayer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JavaPlayer {
+    pub name: String,
+    /// The player's uuid in its hyphenated form.
+    pub id: String,
+}
+
+/// Deserializes a chat component (a string, an object with text and extra, or an array of them) to its text.
+fn component_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    fn flatten(component: &Value, text: &mut String) {
+        match component {
+            Value::String(s) => text.push_str(s),
+            Value::Array(parts) => parts.iter().for_each(|part| flatten(part, text)),
+            Value::Object(object) => {
+                if let Some(Value::String(s)) = object.get("text") {
+                    text.push_str(s);
+                }
+                if let Some(extra) = object.get("extra") {
+                    flatten(extra, text);
+                }
+            },
+            _ => {},
+        }
+    }
+    let mut text = String::new();
+    flatten(&Value::deserialize(deserializer)?, &mut text);
+    Ok(text)
+}
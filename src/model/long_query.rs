@@ -1,3 +1,11 @@
+use crate::model::{AddrMismatch, CompactStatus, ExtraMap, MotdSpan, Plugin, SecurityFlags, SoftwareFamily};
+use crate::utils;
+use core::fmt;
+use core::net::SocketAddr;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 #[allow(dead_code)]
 /// LongQuery is a model of data returned by a STAT request
 ///
@@ -20,5 +28,109 @@ pub struct LongQuery {
     pub map_name: String,
     pub host_name: String,
     pub host_ip: String,
-    pub host_port: u16
-}
\ No newline at end of file
+    pub host_port: u16,
+    /// Every key value pair the server sent that isn't one of the fields above, e.g. `bukkit_version`.
+    pub extra: ExtraMap
+}
+
+impl LongQuery {
+    /// Returns a fixed-size summary of this result, see [CompactStatus](crate::model::CompactStatus)
+    pub fn compact_status(&self) -> CompactStatus {
+        CompactStatus::new(self.player_count, self.max_players)
+    }
+
+    /// Returns how complete this response is, from 0 to 1.
+    ///
+    /// This is the share of modeled fields the server actually filled in, an empty string
+    /// counts as missing and so does an empty player list while players are reported online.
+    pub fn completeness(&self) -> f32 {
+        let strings = [
+            &self.server_software, &self.plugins, &self.version, &self.whitelist, &self.game_name,
+            &self.game_mode, &self.map_name, &self.host_name, &self.host_ip,
+        ];
+        // player_count, max_players and host_port have to be present for the query to succeed at all
+        let numbers = 3;
+        let populated = strings.iter().filter(|s| !s.is_empty()).count()
+            + (self.player_count == 0 || !self.players.is_empty()) as usize
+            + numbers;
+        populated as f32 / (strings.len() + 1 + numbers) as f32
+    }
+
+    /// Returns the server software and version as one display string, like `"Paper 1.20.1"`.
+    ///
+    /// If either of them is empty only the other one is returned.
+    pub fn software_version(&self) -> String {
+        match (self.server_software.trim(), self.version.trim()) {
+            (software, "") => software.to_string(),
+            ("", version) => version.to_string(),
+            (software, version) => format!("{} {}", software, version),
+        }
+    }
+
+    /// Parses the plugins value into a list of [Plugin](crate::model::Plugin)s.
+    ///
+    /// The server software prefix is skipped. Empty if the server lists no plugins,
+    /// or doesn't expose them at all. The version notation depends on the [software family](LongQuery::software_family).
+    pub fn plugin_list(&self) -> Vec<Plugin> {
+        Plugin::parse_list(&self.plugins, self.software_family())
+    }
+
+    /// Returns which server software family the server runs, see [SoftwareFamily](crate::model::SoftwareFamily).
+    ///
+    /// Based on the server software (`server_engine`) and game name (`game_id`), so servers
+    /// spelling the same software differently end up in the same family.
+    pub fn software_family(&self) -> SoftwareFamily {
+        SoftwareFamily::from_query(&self.server_software, &self.game_name)
+    }
+
+    /// Returns whether the server has a whitelist enabled.
+    ///
+    /// Understands the `on`/`off`, `true`/`false` and `1`/`0` spellings server software uses,
+    /// ignoring case. None if the whitelist value is anything else.
+    pub fn is_whitelisted(&self) -> Option<bool> {
+        parse_flag(&self.whitelist)
+    }
+
+    /// Returns the whitelist and online mode flags together, see [SecurityFlags](crate::model::SecurityFlags).
+    ///
+    /// Online mode isn't a standard key, it's read from an `online-mode` or `onlinemode` key in
+    /// [extra](LongQuery::extra) (ignoring case) with the same spellings as [is_whitelisted](LongQuery::is_whitelisted).
+    pub fn security_flags(&self) -> SecurityFlags {
+        let online_mode = self.extra.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("online-mode") || key.eq_ignore_ascii_case("onlinemode"))
+            .and_then(|(_, value)| parse_flag(value));
+        SecurityFlags { whitelist: self.is_whitelisted(), online_mode }
+    }
+
+    /// Returns the host ip and port the server reports about itself as an address.
+    ///
+    /// None if the host isn't an IPv4 or IPv6 literal, e.g. when the server reports a hostname,
+    /// which isn't resolved.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        utils::host_addr(&self.host_ip, self.host_port)
+    }
+
+    /// Compares the host address the server reports about itself against the address it was queried at.
+    ///
+    /// Returns None if both ip and port match, see [AddrMismatch](crate::model::AddrMismatch).
+    pub fn host_mismatch(&self, queried: SocketAddr) -> Option<AddrMismatch> {
+        AddrMismatch::between(&self.host_ip, self.host_port, queried)
+    }
+}
+
+/// Formats as `hostname (players/max) - map` followed by the software version on a second line.
+impl fmt::Display for LongQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}/{}) - {}\n{}", MotdSpan::strip(&self.host_name), self.player_count, self.max_players,
+               self.map_name, self.software_version())
+    }
+}
+
+/// Parses a boolean server setting, in any of the spellings server software uses.
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "on" | "true" | "1" | "yes" => Some(true),
+        "off" | "false" | "0" | "no" => Some(false),
+        _ => None
+    }
+}
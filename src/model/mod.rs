@@ -2,7 +2,36 @@ mod long_query;
 mod short_query;
 pub mod packet;
 mod raknet_pong;
+mod compact_status;
+mod edition;
+mod query_data;
+mod addr_mismatch;
+mod motd_span;
+mod plugin;
+mod server_profile;
+mod software_family;
+mod security_flags;
+mod query_outcome;
+#[cfg(feature = "java")]
+mod java_status;
 
 pub use long_query::LongQuery;
 pub use short_query::ShortQuery;
-pub use raknet_pong::RakNetPong;
\ No newline at end of file
+pub use raknet_pong::RakNetPong;
+pub use compact_status::CompactStatus;
+pub use edition::Edition;
+pub use query_data::QueryData;
+pub use addr_mismatch::AddrMismatch;
+pub use motd_span::MotdSpan;
+pub use plugin::Plugin;
+pub use server_profile::ServerProfile;
+pub use software_family::SoftwareFamily;
+pub use security_flags::SecurityFlags;
+pub use query_outcome::QueryOutcome;
+#[cfg(feature = "java")]
+pub use java_status::{JavaStatus, JavaVersion, JavaPlayers, JavaPlayer};
+
+/// The map unmodeled long query keys are kept in, see [LongQuery::extra](crate::model::LongQuery::extra).
+///
+/// A BTreeMap whether or not the `std` feature is on, so turning it on doesn't change the type.
+pub type ExtraMap = alloc::collections::BTreeMap<alloc::string::String, alloc::string::String>;
@@ -0,0 +1,66 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// MotdSpan is a run of motd text sharing the same `§` color and format codes
+///
+/// The color is the code character (`'0'..='9'`, `'a'..='f'`), None meaning the client's default.
+/// A color code clears the formatting before it and `§r` clears both, like the game does.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MotdSpan {
+    pub text: String,
+    pub color: Option<char>,
+    pub obfuscated: bool,
+    pub bold: bool,
+    pub strikethrough: bool,
+    pub underline: bool,
+    pub italic: bool,
+}
+
+impl MotdSpan {
+    /// Splits a motd into spans, dropping the `§x` codes.
+    ///
+    /// A `§` that isn't followed by one of `0-9a-fk-or` is kept as text.
+    pub(crate) fn parse(motd: &str) -> Vec<MotdSpan> {
+        let mut spans = Vec::new();
+        let mut current = MotdSpan::default();
+        let mut chars = motd.chars().peekable();
+        while let Some(c) = chars.next() {
+            let code = match chars.peek() {
+                Some(&code) if c == '§' && is_code(code) => code.to_ascii_lowercase(),
+                _ => {
+                    current.text.push(c);
+                    continue;
+                }
+            };
+            chars.next();
+            let style = MotdSpan { text: String::new(), ..current.clone() };
+            let next = match code {
+                '0'..='9' | 'a'..='f' => MotdSpan { color: Some(code), ..MotdSpan::default() },
+                'k' => MotdSpan { obfuscated: true, ..style },
+                'l' => MotdSpan { bold: true, ..style },
+                'm' => MotdSpan { strikethrough: true, ..style },
+                'n' => MotdSpan { underline: true, ..style },
+                'o' => MotdSpan { italic: true, ..style },
+                _ => MotdSpan::default(),
+            };
+            let finished = core::mem::replace(&mut current, next);
+            if !finished.text.is_empty() {
+                spans.push(finished);
+            }
+        }
+        if !current.text.is_empty() {
+            spans.push(current);
+        }
+        spans
+    }
+
+    /// Removes the `§x` codes from a motd.
+    pub(crate) fn strip(motd: &str) -> String {
+        MotdSpan::parse(motd).into_iter().map(|span| span.text).collect()
+    }
+}
+
+fn is_code(code: char) -> bool {
+    matches!(code.to_ascii_lowercase(), '0'..='9' | 'a'..='f' | 'k'..='o' | 'r')
+}
@@ -1,4 +1,25 @@
+//! The ids and magic values of the raknet and GS3/GS4 query protocols.
+
+use core::time::Duration;
+
+/// Starts every GS3/GS4 query request.
 pub const MAGIC: u16 = 0xFEFD;
+/// The GS3/GS4 stat request and response id.
 pub const STAT: u8 = 0x00;
+/// The GS3/GS4 handshake (challenge token) request and response id.
 pub const HANDSHAKE: u8 = 0x09;
-pub const PLAYER_KEY: [u8; 11] = [0x00, 0x01, b'p', b'l', b'a', b'y', b'e', b'r', b'_', 0x00, 0x00];
\ No newline at end of file
+/// Minecraft only reads the lower 4 bits of every session id byte, so session ids are masked with this before being sent.
+pub const SESSION_ID_MASK: i32 = 0x0F0F0F0F;
+/// The raknet ID_UNCONNECTED_PING id.
+pub const UNCONNECTED_PING: u8 = 0x01;
+/// The raknet ID_UNCONNECTED_PONG id.
+pub const UNCONNECTED_PONG: u8 = 0x1C;
+/// Separates the key value section of a full stat response from its player list.
+pub const PLAYER_KEY: [u8; 11] = [0x00, 0x01, b'p', b'l', b'a', b'y', b'e', b'r', b'_', 0x00, 0x00];
+/// The magic raknet uses to mark offline (unconnected) messages.
+pub const OFFLINE_MESSAGE_DATA_ID: [u8; 16] = [0x00, 0xff, 0xff, 0x00, 0xfe, 0xfe, 0xfe, 0xfe, 0xfd, 0xfd, 0xfd, 0xfd, 0x12, 0x34, 0x56, 0x78];
+/// Follows the session id of a legacy (pre-GS4) GS3 full stat request instead of a challenge token:
+/// all server info, all players, all teams and that split responses are understood.
+pub const LEGACY_FULL_STAT_FLAGS: [u8; 4] = [0xFF, 0xFF, 0xFF, 0x01];
+/// How long a GS4 challenge token stays valid, servers rotate them every 30 seconds.
+pub const TOKEN_LIFETIME: Duration = Duration::from_secs(30);
@@ -0,0 +1,48 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::model::SoftwareFamily;
+
+/// Plugin is one entry of a server's plugin list, see [LongQuery::plugin_list](crate::model::LongQuery::plugin_list)
+///
+/// Plugins a server lists without a version have None as their version.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    pub name: String,
+    pub version: Option<String>
+}
+
+impl Plugin {
+    /// Parses a GS4 plugins value into its plugins.
+    ///
+    /// Bukkit (`CraftBukkit on Bukkit 1.20.1: PluginA 1.0; PluginB 2.3`) and PocketMine/Nukkit
+    /// (`PocketMine-MP 4.0.0: PluginA 1.0; PluginB 2.3`) share a layout, the server software
+    /// up to the first `:` followed by `;` separated `name version` entries.
+    /// A value without a `:` is only the server software, so no plugins.
+    ///
+    /// They differ in version notation, Bedrock plugins usually put a `v` in front of the version
+    /// (`EconomyAPI v5.7`). For the [Bedrock](SoftwareFamily::Bedrock) family it's dropped, so versions
+    /// look the same whichever software sent them, Bukkit versions are kept as they are.
+    pub(crate) fn parse_list(plugins: &str, family: SoftwareFamily) -> Vec<Self> {
+        let entries = match plugins.split_once(':') {
+            Some((_, entries)) => entries,
+            None => return Vec::new()
+        };
+        entries.split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.rsplit_once(' ') {
+                Some((name, version)) => Plugin { name: name.trim_end().to_string(), version: Some(normalize_version(version, family).to_string()) },
+                None => Plugin { name: entry.to_string(), version: None }
+            })
+            .collect()
+    }
+}
+
+/// Drops the `v` in front of a Bedrock plugin's version, e.g. `v5.7` becomes `5.7`.
+fn normalize_version(version: &str, family: SoftwareFamily) -> &str {
+    match version.strip_prefix(['v', 'V']) {
+        Some(number) if family == SoftwareFamily::Bedrock && number.starts_with(|c: char| c.is_ascii_digit()) => number,
+        _ => version
+    }
+}
@@ -0,0 +1,75 @@
+use crate::model::{Edition, LongQuery, RakNetPong, ShortQuery};
+
+/// QueryData gives access to the fields every query result has in common
+///
+/// This lets code work generically over [ShortQuery](crate::model::ShortQuery),
+/// [LongQuery](crate::model::LongQuery) and [RakNetPong](crate::model::RakNetPong).
+pub trait QueryData {
+    /// How many players are currently online
+    fn players(&self) -> usize;
+    /// How many players the server allows at once
+    fn max_players(&self) -> usize;
+    /// The (first line of the) server motd
+    fn motd(&self) -> &str;
+    /// The edition of the server, if the response tells
+    fn edition(&self) -> Option<Edition>;
+}
+
+impl QueryData for ShortQuery {
+    fn players(&self) -> usize {
+        self.players
+    }
+
+    fn max_players(&self) -> usize {
+        self.max_players
+    }
+
+    fn motd(&self) -> &str {
+        &self.motd
+    }
+
+    /// BASIC STAT only reports the gametype (SMP) so this is always None
+    fn edition(&self) -> Option<Edition> {
+        None
+    }
+}
+
+impl QueryData for LongQuery {
+    fn players(&self) -> usize {
+        self.player_count
+    }
+
+    fn max_players(&self) -> usize {
+        self.max_players
+    }
+
+    fn motd(&self) -> &str {
+        &self.host_name
+    }
+
+    fn edition(&self) -> Option<Edition> {
+        match self.game_name.as_str() {
+            "MINECRAFTPE" => Some(Edition::Bedrock),
+            "MINECRAFT" => Some(Edition::Java),
+            _ => None
+        }
+    }
+}
+
+impl QueryData for RakNetPong {
+    fn players(&self) -> usize {
+        self.player_count
+    }
+
+    fn max_players(&self) -> usize {
+        self.max_player_count
+    }
+
+    fn motd(&self) -> &str {
+        self.motd_lines().0
+    }
+
+    fn edition(&self) -> Option<Edition> {
+        RakNetPong::edition(self).known()
+    }
+}
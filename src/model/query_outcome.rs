@@ -0,0 +1,51 @@
+use crate::{Error, Result};
+
+/// QueryOutcome is a query result split into a server being online or offline
+///
+/// Server browsers mostly want to show a server as up or down. A server that doesn't answer in time
+/// or can't be reached is [Offline](QueryOutcome::Offline), which is an expected outcome rather than an error.
+/// Anything else that goes wrong (e.g. a response that can't be parsed) is still an error.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryOutcome<T> {
+    Online(T),
+    Offline,
+}
+
+impl<T> QueryOutcome<T> {
+    /// Sorts a query result into online, offline or an error.
+    ///
+    /// A [Timeout](crate::Error::Timeout) is offline, and so are socket errors saying the remote
+    /// can't be reached (refused, reset, host or network unreachable).
+    pub fn from_result(result: Result<T>) -> Result<Self> {
+        match result {
+            Ok(data) => Ok(QueryOutcome::Online(data)),
+            Err(e) if is_offline(&e) => Ok(QueryOutcome::Offline),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        matches!(self, QueryOutcome::Online(_))
+    }
+
+    /// The query result if the server is online.
+    pub fn online(self) -> Option<T> {
+        match self {
+            QueryOutcome::Online(data) => Some(data),
+            QueryOutcome::Offline => None,
+        }
+    }
+}
+
+/// Whether the error means the server didn't answer, rather than that it answered with something wrong.
+fn is_offline(e: &Error) -> bool {
+    match e {
+        Error::Timeout => true,
+        #[cfg(feature = "std")]
+        Error::Io(e) => matches!(e.kind(),
+            std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::HostUnreachable | std::io::ErrorKind::NetworkUnreachable),
+        _ => false,
+    }
+}
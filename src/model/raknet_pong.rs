@@ -1,3 +1,9 @@
+use crate::model::{CompactStatus, Edition, MotdSpan};
+use core::fmt;
+use core::net::SocketAddr;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 #[allow(dead_code)]
 /// RakNetPong is a model of data returned by raknet Unconnected Ping
 ///
@@ -8,7 +14,13 @@
 ///
 #[derive(Debug)]
 pub struct RakNetPong {
+    /// The timestamp the ping was sent with, echoed back by the server (milliseconds since the unix epoch).
+    pub ping_time:         i64,
+    /// The server's raknet GUID, the same across every address pointing at one server.
+    pub server_guid:       i64,
     pub game_edition:      String,
+    /// The first motd line followed by the second one if the server sent one,
+    /// see [motd_line1](RakNetPong::motd_line1) and [motd_line2](RakNetPong::motd_line2).
     pub motd:              Vec<String>,
     pub protocol_version:  usize,
     pub game_version:      String,
@@ -17,6 +29,133 @@ pub struct RakNetPong {
     pub server_uid:        String,
     pub game_mode:         Option<String>,
     pub game_mode_integer: Option<usize>,
+    /// The IPv4 game port the server advertises, clients should connect to this one.
     pub port:              Option<u16>,
-    pub port_v6:           Option<u16>
-}
\ No newline at end of file
+    /// The IPv6 game port the server advertises, sent right after the IPv4 one by newer servers.
+    pub port_v6:           Option<u16>,
+    /// Whether nintendo switch players are kept from joining, only sent by newer servers.
+    pub nintendo_limited:  Option<bool>,
+    /// Whether the server runs in editor mode, only sent by newer servers.
+    pub editor_mode:       Option<bool>,
+    /// The address the pong was received from, None if it wasn't received by a [Client](crate::Client).
+    pub remote:            Option<SocketAddr>
+}
+
+impl RakNetPong {
+    /// Returns a fixed-size summary of this result, see [CompactStatus](crate::model::CompactStatus)
+    pub fn compact_status(&self) -> CompactStatus {
+        CompactStatus::new(self.player_count, self.max_player_count)
+    }
+
+    /// Returns the two motd lines of the pong.
+    ///
+    /// The first line is the server name (field 1 of the pong) and the second is
+    /// usually the level/world name (field 7), which older servers don't send.
+    /// The first line is empty if `motd` is.
+    pub fn motd_lines(&self) -> (&str, Option<&str>) {
+        (self.motd.first().map_or("", String::as_str), self.motd.get(1).map(String::as_str))
+    }
+
+    /// Returns the edition the server runs, from its `game_edition` id.
+    ///
+    /// [Unknown](crate::model::Edition::Unknown) for ids other than `MCPE` and `MCEE`.
+    pub fn edition(&self) -> Edition {
+        Edition::from_raknet(&self.game_edition)
+    }
+
+    /// Returns the primary motd line, the server name shown in the server list.
+    pub fn motd_line1(&self) -> &str {
+        self.motd_lines().0
+    }
+
+    /// Returns the secondary motd line, usually the level/world name, None if the server didn't send one.
+    pub fn motd_line2(&self) -> Option<&str> {
+        self.motd_lines().1
+    }
+
+    /// Returns the motd lines without their `§` color and format codes, joined by a newline.
+    pub fn plain_motd(&self) -> String {
+        MotdSpan::strip(&self.motd.join("\n"))
+    }
+
+    /// Splits the motd lines, joined by a newline, into text segments with their color and format.
+    ///
+    /// See [MotdSpan](crate::model::MotdSpan), formatting carries over into the second line.
+    pub fn motd_spans(&self) -> Vec<MotdSpan> {
+        MotdSpan::parse(&self.motd.join("\n"))
+    }
+
+    /// Returns the port clients should connect to.
+    ///
+    /// This is the IPv4 port advertised in the pong, which can differ from the port that was pinged.
+    /// Falls back to the pinged port and then to the default bedrock port (19132) if neither is known.
+    pub fn connect_port(&self) -> u16 {
+        self.port.or_else(|| self.remote.map(|remote| remote.port())).unwrap_or(19132)
+    }
+
+    /// Returns the name of the gamemode.
+    ///
+    /// This is the gamemode string of the pong when the server sent one, otherwise
+    /// the numeric gamemode is mapped to its name (1 = Survival, 2 = Creative, 3 = Adventure).
+    /// The pong's numbers are one above the game's own gamemode ids, BDS sends `Survival;1`.
+    pub fn game_mode_name(&self) -> Option<&str> {
+        match &self.game_mode {
+            Some(name) if !name.is_empty() => Some(name),
+            _ => match self.game_mode_integer? {
+                1 => Some("Survival"),
+                2 => Some("Creative"),
+                3 => Some("Adventure"),
+                _ => None
+            }
+        }
+    }
+
+    /// Returns the release the protocol version belongs to, e.g. `594` -> `"1.20.10"`.
+    ///
+    /// Looked up from a table of known Bedrock protocol versions, unlike `game_version` which is
+    /// whatever the server claims to run. A protocol shared by several releases maps to the first of them.
+    pub fn version_name(&self) -> Option<&'static str> {
+        BEDROCK_PROTOCOLS.binary_search_by_key(&self.protocol_version, |(protocol, _)| *protocol)
+            .ok()
+            .map(|i| BEDROCK_PROTOCOLS[i].1)
+    }
+
+    /// Parses the major and minor version out of `game_version` (`"1.20.15"` -> `(1, 20)`).
+    ///
+    /// Prerelease suffixes such as `"1.21.0-beta"` or `"1.20 preview"` are ignored.
+    pub fn major_minor(&self) -> Option<(u16, u16)> {
+        let mut parts = self.game_version.trim().trim_start_matches('v').split('.');
+        let major = leading_number(parts.next()?)?;
+        let minor = leading_number(parts.next()?)?;
+        Some((major, minor))
+    }
+}
+
+/// Formats as `motd (players/max) - world`, leaving out the world if the server didn't send one.
+impl fmt::Display for RakNetPong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (motd, world) = self.motd_lines();
+        write!(f, "{} ({}/{})", MotdSpan::strip(motd), self.player_count, self.max_player_count)?;
+        match world {
+            Some(world) if !world.is_empty() => write!(f, " - {}", MotdSpan::strip(world)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Known Bedrock protocol versions and the release they first shipped in, sorted by protocol.
+const BEDROCK_PROTOCOLS: [(usize, &str); 40] = [
+    (291, "1.7.0"), (313, "1.8.0"), (332, "1.9.0"), (340, "1.10.0"), (354, "1.11.0"),
+    (361, "1.12.0"), (388, "1.13.0"), (389, "1.14.0"), (390, "1.14.60"), (407, "1.16.0"),
+    (408, "1.16.20"), (419, "1.16.100"), (422, "1.16.200"), (428, "1.16.210"), (431, "1.16.220"),
+    (440, "1.17.0"), (448, "1.17.10"), (465, "1.17.30"), (471, "1.17.40"), (475, "1.18.0"),
+    (486, "1.18.10"), (503, "1.18.30"), (527, "1.19.0"), (534, "1.19.10"), (544, "1.19.20"),
+    (545, "1.19.21"), (554, "1.19.30"), (557, "1.19.40"), (560, "1.19.50"), (567, "1.19.60"),
+    (568, "1.19.63"), (575, "1.19.70"), (582, "1.19.80"), (589, "1.20.0"), (594, "1.20.10"),
+    (618, "1.20.30"), (622, "1.20.40"), (630, "1.20.50"), (649, "1.20.60"), (662, "1.20.70"),
+];
+
+fn leading_number(part: &str) -> Option<u16> {
+    let end = part.find(|c: char| !c.is_ascii_digit()).unwrap_or(part.len());
+    part[..end].parse().ok()
+}
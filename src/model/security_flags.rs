@@ -0,0 +1,11 @@
+/// SecurityFlags bundles the access settings a server reports in its long query
+///
+/// Each flag is None if the server doesn't report it, or reports it in a spelling that isn't understood.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityFlags {
+    /// Whether only whitelisted players can join, see [LongQuery::is_whitelisted](crate::model::LongQuery::is_whitelisted).
+    pub whitelist: Option<bool>,
+    /// Whether players have to be authenticated with their account, from the custom `online-mode` key.
+    pub online_mode: Option<bool>,
+}
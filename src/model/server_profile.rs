@@ -0,0 +1,99 @@
+use crate::model::{Edition, ExtraMap, LongQuery, QueryData, RakNetPong};
+use core::net::SocketAddr;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// ServerProfile is everything a [RakNetPong](crate::model::RakNetPong) and a
+/// [LongQuery](crate::model::LongQuery) tell about a server, merged into one model
+///
+/// Fields both responses carry are only kept once. Player counts come from the long query
+/// so they agree with its player list, while the motd, version and ports prefer the pong,
+/// falling back to the long query when the pong left them out.
+///
+#[derive(Debug)]
+pub struct ServerProfile {
+    /// The server's raknet GUID.
+    pub server_guid: i64,
+    pub game_edition: String,
+    /// The motd lines from the pong.
+    pub motd: Vec<String>,
+    /// The hostname from the long query, usually the first motd line.
+    pub host_name: String,
+    pub protocol_version: usize,
+    pub game_version: String,
+    pub server_software: String,
+    pub plugins: String,
+    pub whitelist: String,
+    pub players: Vec<String>,
+    pub player_count: usize,
+    pub max_players: usize,
+    pub server_uid: String,
+    pub game_mode: Option<String>,
+    pub game_mode_integer: Option<usize>,
+    pub map_name: String,
+    pub host_ip: String,
+    /// The IPv4 game port, from the pong or else the long query's host port.
+    pub port: u16,
+    pub port_v6: Option<u16>,
+    pub nintendo_limited: Option<bool>,
+    pub editor_mode: Option<bool>,
+    /// The long query's key value pairs that aren't modeled, see [LongQuery::extra](crate::model::LongQuery::extra).
+    pub extra: ExtraMap,
+    /// The address the pong was received from.
+    pub remote: Option<SocketAddr>
+}
+
+impl ServerProfile {
+    /// Merges a pong and a long query of the same server, like [Client::full_profile](crate::Client::full_profile) does.
+    ///
+    /// Useful for responses decoded with the [parsers](crate::parse) without a client.
+    pub fn new(pong: RakNetPong, query: LongQuery) -> Self {
+        let non_empty = |s: String| Some(s).filter(|s| !s.is_empty());
+        let game_version = if pong.game_version.is_empty() { query.version } else { pong.game_version };
+        let query_mode = query.game_mode;
+        let game_mode = pong.game_mode.and_then(non_empty).or_else(|| non_empty(query_mode));
+        ServerProfile {
+            server_guid: pong.server_guid,
+            game_edition: pong.game_edition,
+            motd: pong.motd,
+            host_name: query.host_name,
+            protocol_version: pong.protocol_version,
+            game_version,
+            server_software: query.server_software,
+            plugins: query.plugins,
+            whitelist: query.whitelist,
+            players: query.players,
+            player_count: query.player_count,
+            max_players: query.max_players,
+            server_uid: pong.server_uid,
+            game_mode,
+            game_mode_integer: pong.game_mode_integer,
+            map_name: query.map_name,
+            host_ip: query.host_ip,
+            port: pong.port.unwrap_or(query.host_port),
+            port_v6: pong.port_v6,
+            nintendo_limited: pong.nintendo_limited,
+            editor_mode: pong.editor_mode,
+            extra: query.extra,
+            remote: pong.remote,
+        }
+    }
+}
+
+impl QueryData for ServerProfile {
+    fn players(&self) -> usize {
+        self.player_count
+    }
+
+    fn max_players(&self) -> usize {
+        self.max_players
+    }
+
+    fn motd(&self) -> &str {
+        self.motd.first().map_or(&self.host_name, |line| line)
+    }
+
+    fn edition(&self) -> Option<Edition> {
+        Edition::from_raknet(&self.game_edition).known()
+    }
+}
@@ -1,5 +1,11 @@
-#[allow(dead_code)]
+use crate::model::{AddrMismatch, CompactStatus, MotdSpan};
+use crate::utils;
+use core::fmt;
+use core::net::SocketAddr;
+use alloc::string::String;
+use alloc::vec::Vec;
 
+#[allow(dead_code)]
 /// ShortQuery is a model of data returned by GS3 BASIC STAT
 ///
 /// This data includes game_type (SMP) to host_ip
@@ -18,4 +24,43 @@ pub struct ShortQuery {
     /// The port that the server is running on
     pub host_port: u16,
     pub host_ip: String,
-}
\ No newline at end of file
+}
+
+impl ShortQuery {
+    /// Returns a fixed-size summary of this result, see [CompactStatus](crate::model::CompactStatus)
+    pub fn compact_status(&self) -> CompactStatus {
+        CompactStatus::new(self.players, self.max_players)
+    }
+
+    /// Returns the host ip and port the server reports about itself as an address.
+    ///
+    /// None if the host isn't an IPv4 or IPv6 literal, e.g. when the server reports a hostname,
+    /// which isn't resolved.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        utils::host_addr(&self.host_ip, self.host_port)
+    }
+
+    /// Compares the host address the server reports about itself against the address it was queried at.
+    ///
+    /// Returns None if both ip and port match, see [AddrMismatch](crate::model::AddrMismatch).
+    pub fn host_mismatch(&self, queried: SocketAddr) -> Option<AddrMismatch> {
+        AddrMismatch::between(&self.host_ip, self.host_port, queried)
+    }
+
+    /// Returns the motd without its `§` color and format codes.
+    pub fn plain_motd(&self) -> String {
+        MotdSpan::strip(&self.motd)
+    }
+
+    /// Splits the motd into text segments with their color and format, see [MotdSpan](crate::model::MotdSpan)
+    pub fn motd_spans(&self) -> Vec<MotdSpan> {
+        MotdSpan::parse(&self.motd)
+    }
+}
+
+/// Formats as `motd (players/max) - map`, with the motd's `§` codes stripped.
+impl fmt::Display for ShortQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}/{}) - {}", self.plain_motd(), self.players, self.max_players, self.map)
+    }
+}
@@ -0,0 +1,43 @@
+/// The server software family a [LongQuery](crate::model::LongQuery) came from, see
+/// [LongQuery::software_family](crate::model::LongQuery::software_family)
+///
+/// Forks are reported as the family they're based on, e.g. Purpur as Paper.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoftwareFamily {
+    Paper,
+    Spigot,
+    Bukkit,
+    Vanilla,
+    /// Any Bedrock server software, like PocketMine-MP, Nukkit or the Bedrock Dedicated Server.
+    Bedrock,
+    /// The server reported software this crate doesn't know.
+    Unknown
+}
+
+impl SoftwareFamily {
+    /// Normalizes the `server_engine` and `game_id` values of a long query, ignoring case.
+    ///
+    /// The game id only decides when the engine is empty or unrecognized.
+    pub(crate) fn from_query(server_engine: &str, game_id: &str) -> Self {
+        let engine = server_engine.trim().to_ascii_lowercase();
+        let has = |names: &[&str]| names.iter().any(|name| engine.contains(name));
+        if has(&["paper", "purpur", "folia", "pufferfish"]) {
+            SoftwareFamily::Paper
+        } else if has(&["spigot"]) {
+            SoftwareFamily::Spigot
+        } else if has(&["bukkit"]) {
+            SoftwareFamily::Bukkit
+        } else if has(&["pocketmine", "nukkit", "bedrock"]) {
+            SoftwareFamily::Bedrock
+        } else if has(&["vanilla"]) {
+            SoftwareFamily::Vanilla
+        } else {
+            match (engine.as_str(), game_id.trim().to_ascii_uppercase().as_str()) {
+                (_, "MINECRAFTPE") => SoftwareFamily::Bedrock,
+                ("", "MINECRAFT") => SoftwareFamily::Vanilla,
+                _ => SoftwareFamily::Unknown
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Parses raw response datagrams into the [models](crate::model), separate from any socket I/O.
+//!
+//! Every parser takes the whole datagram as received, packet id included, and returns an
+//! [Error](crate::Error) for input it can't make sense of instead of panicking, so they can be
+//! fed arbitrary bytes, e.g. by a fuzzer or captured traffic.
+//! They only need `alloc`, so they're also available without the `std` feature.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use crate::{Error, Result};
+use crate::model::{packet, LongQuery, RakNetPong, ShortQuery};
+use crate::utils::{self, read_nulltermed_str, slice_index};
+
+/// Length of a full stat response before its key value section:
+/// packet id, session id and the constant `splitnum\0\x80\0` padding.
+pub(crate) const FULL_STAT_HEADER_LEN: usize = 16;
+
+/// The full stat keys that map to a named field of [LongQuery](crate::model::LongQuery).
+pub(crate) const LONG_QUERY_KEYS: [&str; 12] = [
+    "server_engine", "plugins", "version", "whitelist", "numplayers", "maxplayers",
+    "game_id", "gametype", "map", "hostname", "hostip", "hostport",
+];
+
+/// Where the offline message data id sits in a pong that puts the server guid after it:
+/// right after the packet id and time.
+const GUID_AFTER_MAGIC_INDEX: usize = 9;
+
+/// Where the offline message data id sits in a pong that puts the server guid before it:
+/// after the packet id, time and server guid.
+const GUID_FIRST_MAGIC_INDEX: usize = 17;
+
+/// Parses a raw unconnected pong datagram into a [RakNetPong](crate::model::RakNetPong)
+///
+/// The server id string is a `;` separated list laid out as
+/// `edition;motd line 1;protocol;version;players;max players;server uid;motd line 2;gamemode;gamemode id;port;port v6;nintendo limited;editor mode;...`
+///
+/// Fields are located relative to the offline message data id, since implementations
+/// disagree on whether the server guid goes before or after it. Anything that isn't an
+/// unconnected pong with the id in one of those two places is rejected.
+pub fn parse_pong(buf: &[u8]) -> Result<RakNetPong> {
+    expect_packet(buf, packet::UNCONNECTED_PONG)?;
+    let magic = [GUID_FIRST_MAGIC_INDEX, GUID_AFTER_MAGIC_INDEX].iter().copied()
+        .find(|i| buf.get(*i..*i + packet::OFFLINE_MESSAGE_DATA_ID.len()) == Some(&packet::OFFLINE_MESSAGE_DATA_ID[..]))
+        .ok_or_else(|| Error::MalformedResponse("Pong is missing the offline message data id".to_string()))?;
+    let mut start = magic + packet::OFFLINE_MESSAGE_DATA_ID.len();
+    let guid_index = if magic < GUID_FIRST_MAGIC_INDEX {
+        // the server guid follows the magic
+        start += 8;
+        magic + packet::OFFLINE_MESSAGE_DATA_ID.len()
+    } else {
+        magic - 8
+    };
+    // skip the length of the server id string
+    start += 2;
+    if buf.len() < start {
+        return Err(Error::MalformedResponse("Pong is too short to hold a server id string".to_string()));
+    }
+    let ping_time = i64::from_be_bytes(buf[1..9].try_into().unwrap());
+    let server_guid = i64::from_be_bytes(buf[guid_index..guid_index + 8].try_into().unwrap());
+    //Split the data into a vector made of Strings
+    let data: Vec<String> = String::from_utf8_lossy(&buf[start..])
+        .split(';').map(String::from).collect();
+    // edition to server uid are sent by every implementation
+    if data.len() < 7 {
+        return Err(Error::MalformedResponse(format!("Pong has {} server id fields, expected at least 7", data.len())));
+    }
+    let mut motd = vec![data[1].clone()];
+    if data.len() > 7 {
+        motd.push(data[7].clone());
+    }
+    let gamemode = data.get(8).cloned();
+    Ok(RakNetPong {
+        ping_time,
+        server_guid,
+        game_edition: data[0].clone(),
+        motd,
+        protocol_version: data[2].parse().map_err(|_| Error::ParseField { field: "protocol_version" })?,
+        game_version: data[3].clone(),
+        player_count: utils::parse_count(&data[4], "player_count")?,
+        max_player_count: utils::parse_count(&data[5], "max_player_count")?,
+        server_uid: data[6].clone(),
+        game_mode: gamemode,
+        game_mode_integer: data.get(9).and_then(|mode| mode.parse().ok()),
+        port: data.get(10).and_then(|port| port.parse().ok()),
+        port_v6: data.get(11).and_then(|port| port.parse().ok()),
+        nintendo_limited: data.get(12).and_then(|flag| parse_flag(flag)),
+        editor_mode: data.get(13).and_then(|flag| parse_flag(flag)),
+        remote: None
+    })
+}
+
+/// Parses a `0`/`1` pong flag, None for anything else (usually an empty field).
+fn parse_flag(flag: &str) -> Option<bool> {
+    match flag {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+/// Splits the body of a full stat response (everything after its header) into the
+/// key value section and the player section.
+///
+/// The body is laid out as
+/// `key\0value\0 ... key\0value\0` `\0` `\x01player_\0` `\0` `name\0 ... name\0` `\0`,
+/// both lists end with an empty string and [PLAYER_KEY](crate::model::packet::PLAYER_KEY) covers
+/// the empty key ending the key value list together with the splitter.
+///
+/// The key value section is returned without the empty key and the player section
+/// without the empty name, anything after that (padding) is ignored.
+/// There is no player section if the splitter is missing, and an empty one if the response ends
+/// right after the splitter, which some servers do instead of sending the empty name while nobody is online.
+///
+/// The protocol has no way to continue a full stat in another datagram, so a player
+/// section that runs out before its empty name is a truncated response and an error,
+/// rather than a silently shortened player list.
+pub(crate) fn split_full_stat(body: &[u8]) -> Result<(&[u8], Option<&[u8]>)> {
+    let player_index = match slice_index(body, &packet::PLAYER_KEY) {
+        Some(pi) => pi,
+        None => return Ok((body, None)),
+    };
+    let players = &body[player_index + packet::PLAYER_KEY.len()..];
+    if players.is_empty() {
+        return Ok((&body[..player_index], Some(players)));
+    }
+    // walk the names up to the first empty one
+    let mut end = 0;
+    while players.get(end) != Some(&0x00) {
+        end += match players.get(end..).and_then(|rest| rest.iter().position(|byte| *byte == 0x00)) {
+            Some(terminator) => terminator + 1,
+            None => return Err(Error::MalformedResponse("Full stat response is truncated, the player list never ends".to_string())),
+        };
+    }
+    // drop the terminator of the last name
+    let players = match &players[..end] {
+        [names @ .., 0x00] => names,
+        names => names,
+    };
+    Ok((&body[..player_index], Some(players)))
+}
+
+/// Splits the player section returned by [split_full_stat] into the player names.
+///
+/// Some proxies put extra tokens between the names, so empty entries and entries that can't be
+/// a name (not UTF-8 or containing control characters) are skipped. Splitting stops once `limit`
+/// names are collected.
+pub(crate) fn player_names(section: &[u8], limit: usize) -> Vec<String> {
+    section.split(|byte| *byte == 0x00)
+        .filter_map(|name| core::str::from_utf8(name).ok())
+        .filter(|name| !name.trim().is_empty() && !name.chars().any(char::is_control))
+        .take(limit)
+        .map(String::from)
+        .collect()
+}
+
+/// Checks the packet id of a reply, failing unless it's the awaited one.
+fn expect_packet(datagram: &[u8], expected: u8) -> Result<()> {
+    match datagram.first() {
+        Some(id) if *id == expected => Ok(()),
+        Some(got) => Err(Error::UnexpectedPacket { expected, got: *got }),
+        None => Err(Error::MalformedResponse("Received an empty datagram".to_string())),
+    }
+}
+
+/// Parses the challenge token out of a handshake reply.
+pub fn parse_challenge_token(reply: &[u8]) -> Result<i32> {
+    expect_packet(reply, packet::HANDSHAKE)?;
+    let token = reply.get(5..reply.len().saturating_sub(1))
+        .ok_or_else(|| Error::MalformedResponse(format!("Handshake response is too short to hold a token: {}", hex::encode(reply))))?;
+    match String::from_utf8_lossy(token).parse() {
+        Ok(token) => Ok(token),
+        Err(_) => Err(Error::MalformedResponse(format!("Invalid Challenge Token Received: {}", hex::encode(reply))))
+    }
+}
+
+/// Parses a basic stat reply into a [ShortQuery](crate::model::ShortQuery).
+pub fn parse_short_stat(datagram: &[u8]) -> Result<ShortQuery> {
+    expect_packet(datagram, packet::STAT)?;
+    let body = datagram.get(5..)
+        .ok_or_else(|| Error::MalformedResponse("Basic stat response is too short to hold a session id".to_string()))?;
+    let mut buf = body;
+    let motd = read_nulltermed_str(&mut buf);
+    let gametype = read_nulltermed_str(&mut buf);
+    let map = read_nulltermed_str(&mut buf);
+    let players = utils::parse_count(&read_nulltermed_str(&mut buf), "players")?;
+    let max_players = utils::parse_count(&read_nulltermed_str(&mut buf), "max_players")?;
+    let host_port = match buf {
+        [low, high, rest @ ..] => {
+            buf = rest;
+            u16::from_le_bytes([*low, *high])
+        },
+        _ => return Err(Error::MalformedResponse("Basic stat response is too short to hold the host port".to_string())),
+    };
+    let host_ip = read_nulltermed_str(&mut buf);
+    Ok(ShortQuery {
+        motd,
+        gametype,
+        map,
+        players,
+        max_players,
+        host_port,
+        host_ip
+    })
+}
+
+/// Parses a full stat reply into a [LongQuery](crate::model::LongQuery).
+///
+/// Keys and values that aren't UTF-8 are decoded lossily.
+pub fn parse_full_stat(datagram: &[u8]) -> Result<LongQuery> {
+    parse_full_stat_requiring(datagram, &[], usize::MAX)
+}
+
+/// Same as [parse_full_stat], failing if any of the required keys is missing and listing at most
+/// `player_limit` players.
+pub(crate) fn parse_full_stat_requiring(datagram: &[u8], keys: &[&str], player_limit: usize) -> Result<LongQuery> {
+    expect_packet(datagram, packet::STAT)?;
+    let body = datagram.get(FULL_STAT_HEADER_LEN..)
+        .ok_or_else(|| Error::MalformedResponse("Full stat response is too short to hold its header".to_string()))?;
+    let (reg_data, player_data) = split_full_stat(body)?;
+    let mut arr = reg_data.split(|byte| byte == &0x00u8).collect::<Vec<&[u8]>>();
+    if arr.len() % 2 != 0 {
+        arr.pop();
+    }
+    let mut reader: BTreeMap<Cow<str>, String> = BTreeMap::new();
+    for pair in arr.chunks(2) {
+        reader.insert(String::from_utf8_lossy(pair[0]), String::from_utf8_lossy(pair[1]).into_owned());
+    }
+    let players = player_data.map(|section| player_names(section, player_limit)).unwrap_or_default();
+    let missing: Vec<&str> = keys.iter().copied().filter(|key| !reader.contains_key(*key)).collect();
+    if !missing.is_empty() {
+        return Err(Error::MalformedResponse(format!("Server response is missing required keys: {}", missing.join(", "))));
+    }
+    let get = |key: &'static str| reader.get(key).ok_or(Error::MissingField(key));
+    let extra = reader.iter()
+        .filter(|(key, _)| !LONG_QUERY_KEYS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect();
+    Ok(LongQuery {
+        server_software: get("server_engine")?.clone(),
+        plugins: get("plugins")?.clone(),
+        version: get("version")?.clone(),
+        whitelist: get("whitelist")?.clone(),
+        players,
+        player_count: get("numplayers")?.parse().map_err(|_| Error::ParseField { field: "player_count" })?,
+        max_players: get("maxplayers")?.parse().map_err(|_| Error::ParseField { field: "max_players" })?,
+        game_name: get("game_id")?.clone(),
+        game_mode: get("gametype")?.clone(),
+        map_name: get("map")?.clone(),
+        host_name: get("hostname")?.clone(),
+        host_ip: get("hostip")?.clone(),
+        host_port: get("hostport")?.parse().map_err(|_| Error::ParseField { field: "host_port" })?,
+        extra
+    })
+}
@@ -0,0 +1,89 @@
+//! Records the datagrams a [Client](crate::Client) sends and receives in the pcap format.
+//!
+//! Only available with the `pcap` feature. The capture uses the raw IP link type,
+//! every datagram gets a synthesized IPv4 (or IPv6) and UDP header so Wireshark can dissect it.
+
+use std::io::{Result, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+use byteorder::{WriteBytesExt, BigEndian, LittleEndian};
+
+/// LINKTYPE_RAW, packets start with an IPv4 or IPv6 header.
+const LINKTYPE_RAW: u32 = 101;
+const UDP: u8 = 17;
+const TTL: u8 = 64;
+
+pub(crate) struct PcapWriter {
+    writer: Box<dyn Write + Send>,
+}
+
+impl PcapWriter {
+    /// Wraps the writer, writing the pcap global header to it.
+    pub(crate) fn new<W: Write + Send + 'static>(mut writer: W) -> Result<Self> {
+        writer.write_u32::<LittleEndian>(0xa1b2c3d4)?;
+        // version 2.4
+        writer.write_u16::<LittleEndian>(2)?;
+        writer.write_u16::<LittleEndian>(4)?;
+        // GMT offset and timestamp accuracy
+        writer.write_i32::<LittleEndian>(0)?;
+        writer.write_u32::<LittleEndian>(0)?;
+        // snapshot length
+        writer.write_u32::<LittleEndian>(u16::MAX as u32)?;
+        writer.write_u32::<LittleEndian>(LINKTYPE_RAW)?;
+        writer.flush()?;
+        Ok(PcapWriter { writer: Box::new(writer) })
+    }
+
+    /// Writes one udp datagram from src to dst as a pcap record.
+    pub(crate) fn record(&mut self, src: SocketAddr, dst: SocketAddr, payload: &[u8]) -> Result<()> {
+        let mut packet = Vec::with_capacity(48 + payload.len());
+        let udp_len = (8 + payload.len()) as u16;
+        match (src.ip(), dst.ip()) {
+            (IpAddr::V4(src_ip), IpAddr::V4(dst_ip)) => {
+                let mut header = vec![0x45, 0x00];
+                header.write_u16::<BigEndian>(20 + udp_len)?;
+                // identification, flags and fragment offset
+                header.write_u32::<BigEndian>(0)?;
+                header.extend_from_slice(&[TTL, UDP, 0x00, 0x00]);
+                header.extend_from_slice(&src_ip.octets());
+                header.extend_from_slice(&dst_ip.octets());
+                let checksum = ipv4_checksum(&header);
+                header[10..12].copy_from_slice(&checksum.to_be_bytes());
+                packet.extend(header);
+            },
+            (src_ip, dst_ip) => {
+                packet.write_u32::<BigEndian>(0x60000000)?;
+                packet.write_u16::<BigEndian>(udp_len)?;
+                packet.extend_from_slice(&[UDP, TTL]);
+                packet.extend_from_slice(&to_ipv6(src_ip).octets());
+                packet.extend_from_slice(&to_ipv6(dst_ip).octets());
+            }
+        }
+        packet.write_u16::<BigEndian>(src.port())?;
+        packet.write_u16::<BigEndian>(dst.port())?;
+        packet.write_u16::<BigEndian>(udp_len)?;
+        // no udp checksum
+        packet.write_u16::<BigEndian>(0)?;
+        packet.extend_from_slice(payload);
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.writer.write_u32::<LittleEndian>(now.as_secs() as u32)?;
+        self.writer.write_u32::<LittleEndian>(now.subsec_micros())?;
+        self.writer.write_u32::<LittleEndian>(packet.len() as u32)?;
+        self.writer.write_u32::<LittleEndian>(packet.len() as u32)?;
+        self.writer.write_all(&packet)?;
+        self.writer.flush()
+    }
+}
+
+fn to_ipv6(ip: IpAddr) -> std::net::Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+fn ipv4_checksum(header: &[u8]) -> u16 {
+    let sum = header.chunks(2).fold(0u32, |sum, word| sum + u16::from_be_bytes([word[0], word[1]]) as u32);
+    !(((sum & 0xffff) + (sum >> 16)) as u16)
+}
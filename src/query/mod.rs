@@ -0,0 +1,41 @@
+use std::future::Future;
+use tokio::net::ToSocketAddrs;
+use crate::{Client, Result};
+use crate::model::{LongQuery, RakNetPong, ShortQuery};
+
+/// Query is the set of queries a [Client](crate::Client) can run
+///
+/// Code that takes a `impl Query` instead of a Client can be handed a fake in tests,
+/// so it doesn't need a server to talk to. The futures are Send, so they can be spawned.
+///
+/// # [Example]
+/// ```
+/// use rsquery::{Query, Result};
+///
+/// async fn is_full(server: &impl Query) -> Result<bool> {
+///     let pong = server.raknet_ping().await?;
+///     Ok(pong.player_count >= pong.max_player_count)
+/// }
+/// ```
+pub trait Query {
+    /// See [Client::raknet_ping](crate::Client::raknet_ping)
+    fn raknet_ping(&self) -> impl Future<Output = Result<RakNetPong>> + Send;
+    /// See [Client::short_query](crate::Client::short_query)
+    fn short_query(&self) -> impl Future<Output = Result<ShortQuery>> + Send;
+    /// See [Client::long_query](crate::Client::long_query)
+    fn long_query(&self) -> impl Future<Output = Result<LongQuery>> + Send;
+}
+
+impl<A: ToSocketAddrs + Send + Sync> Query for Client<A> {
+    fn raknet_ping(&self) -> impl Future<Output = Result<RakNetPong>> + Send {
+        Client::raknet_ping(self)
+    }
+
+    fn short_query(&self) -> impl Future<Output = Result<ShortQuery>> + Send {
+        Client::short_query(self)
+    }
+
+    fn long_query(&self) -> impl Future<Output = Result<LongQuery>> + Send {
+        Client::long_query(self)
+    }
+}
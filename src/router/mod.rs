@@ -0,0 +1,247 @@
+//! Hands the datagrams received on a client's transport to the queries waiting for them.
+//!
+//! Every query registers what reply it's waiting for before sending its request, then a task reading
+//! the transport in the background routes each datagram to the matching query. Replies are matched
+//! by the address they came from and the bytes they echo from the request right after the packet id
+//! (the session id of GS3 replies, the timestamp of pongs), so any number of queries can share one
+//! transport at once. Datagrams no query is waiting for, like late replies to cancelled queries, are discarded.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use crate::transport::{self, AsyncDatagram};
+
+/// The largest datagram udp can carry, relayed ones included.
+const MAX_DATAGRAM: usize = 65536;
+
+/// A datagram routed to a query with when it was received, or the error the transport failed with.
+type Delivery = io::Result<(Vec<u8>, Instant)>;
+
+/// Routes the datagrams received on a transport to the queries waiting for them, see the [module docs](self).
+///
+/// The background task is spawned on the tokio runtime by the first query and stops when the router is dropped,
+/// or when the transport fails to receive (the next query spawns it again).
+pub(crate) struct Router {
+    transport: Arc<dyn AsyncDatagram>,
+    /// Held while sending, transports only wake up the task that polled them last.
+    sending: tokio::sync::Mutex<()>,
+    /// What [expect](Router::expect) receives the queued datagrams into, kept rather than allocated for every query.
+    queued: Mutex<Box<[u8]>>,
+    shared: Arc<Shared>,
+    reader: Mutex<Option<JoinHandle<()>>>,
+    /// The timestamp of the last ping sent, see [ping_time](Router::ping_time).
+    last_ping: AtomicI64,
+}
+
+/// What the router and the background task share.
+struct Shared {
+    waiting: Mutex<Waiting>,
+    /// Has the background task poll the transport again, see [Router::expect].
+    repoll: Notify,
+    /// The SOCKS5 relay every datagram comes through, with a header naming the server that sent it.
+    #[cfg(feature = "socks5")]
+    relay: Option<SocketAddr>,
+}
+
+#[derive(Default)]
+struct Waiting {
+    next_key: u64,
+    queries: Vec<Waiter>,
+}
+
+/// A query waiting for its reply.
+struct Waiter {
+    key: u64,
+    remote: SocketAddr,
+    /// The packet id of the reply.
+    id: u8,
+    /// The bytes the reply echoes from the request, right after the packet id.
+    tag: Vec<u8>,
+    /// False while the query looks at a datagram it was handed, so others waiting for the same reply come first.
+    armed: bool,
+    tx: mpsc::UnboundedSender<Delivery>,
+}
+
+impl Waiter {
+    /// How well the datagram matches this query's reply, 2 if it echoes the tag and 1 for just the packet id.
+    fn matches(&self, datagram: &[u8]) -> u8 {
+        match datagram.first() {
+            Some(id) if *id == self.id && datagram.get(1..1 + self.tag.len()) == Some(&self.tag[..]) => 2,
+            Some(id) if *id == self.id => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// A registered query, receiving the datagrams routed to it until it's dropped.
+pub(crate) struct Expected {
+    key: u64,
+    shared: Arc<Shared>,
+    rx: mpsc::UnboundedReceiver<Delivery>,
+}
+
+impl Router {
+    pub(crate) fn new(transport: Arc<dyn AsyncDatagram>) -> Self {
+        Router::with_shared(transport, Shared::new())
+    }
+
+    /// A router for a transport that receives everything through the SOCKS5 relay at this address.
+    #[cfg(feature = "socks5")]
+    pub(crate) fn relayed(transport: Arc<dyn AsyncDatagram>, relay: SocketAddr) -> Self {
+        Router::with_shared(transport, Shared { relay: Some(relay), ..Shared::new() })
+    }
+
+    fn with_shared(transport: Arc<dyn AsyncDatagram>, shared: Shared) -> Self {
+        Router {
+            transport,
+            sending: tokio::sync::Mutex::new(()),
+            queued: Mutex::new(vec![0u8; MAX_DATAGRAM].into_boxed_slice()),
+            shared: Arc::new(shared),
+            reader: Mutex::new(None),
+            last_ping: AtomicI64::new(0),
+        }
+    }
+
+    /// Sends the datagram to the target, one query at a time, returning when it started sending.
+    ///
+    /// That's after waiting for other queries to send theirs, so the wait doesn't count towards round-trip times.
+    pub(crate) async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<Instant> {
+        let _sending = self.sending.lock().await;
+        let sent = Instant::now();
+        transport::send_to(&*self.transport, buf, target).await?;
+        Ok(sent)
+    }
+
+    pub(crate) fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.transport.local_addr()
+    }
+
+    /// The timestamp to send a ping with, milliseconds since the unix epoch.
+    ///
+    /// Pongs are matched to pings by the timestamp they echo, so it's bumped past the last one
+    /// when several pings are sent within the same millisecond.
+    pub(crate) fn ping_time(&self) -> i64 {
+        let now = crate::encode::unix_millis();
+        let next = |last: i64| now.max(last + 1);
+        self.last_ping.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(next(last))).map_or(now, next)
+    }
+
+    /// Registers a query waiting for a reply from the remote with the packet id, echoing the tag.
+    ///
+    /// Has to be called before sending the request, so the reply can't arrive first. Whatever is
+    /// still queued on the transport was received before the request was sent, so it's routed to the
+    /// queries waiting so far (or discarded) before registering, it can't pass for this query's reply.
+    pub(crate) fn expect(&self, remote: SocketAddr, id: u8, tag: &[u8]) -> Expected {
+        {
+            let mut buf = self.queued.lock().unwrap();
+            // stops at the first error rather than only at WouldBlock, one that keeps coming back would spin here
+            while let Ok((len, from)) = self.transport.try_recv_from(&mut buf) {
+                self.shared.route(&buf[..len], from, Instant::now());
+            }
+        }
+        // try_recv_from may have replaced the background task's waker, so it has to poll again
+        self.shared.repoll.notify_one();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let key = {
+            let mut waiting = self.shared.waiting.lock().unwrap();
+            waiting.next_key += 1;
+            let key = waiting.next_key;
+            waiting.queries.push(Waiter { key, remote, id, tag: tag.to_vec(), armed: true, tx });
+            key
+        };
+        let mut reader = self.reader.lock().unwrap();
+        if reader.as_ref().is_none_or(JoinHandle::is_finished) {
+            *reader = Some(tokio::spawn(read(self.transport.clone(), self.shared.clone())));
+        }
+        Expected { key, shared: self.shared.clone(), rx }
+    }
+}
+
+impl Drop for Router {
+    fn drop(&mut self) {
+        if let Some(reader) = self.reader.get_mut().unwrap() {
+            reader.abort();
+        }
+    }
+}
+
+/// Reads the transport until receiving fails, routing every datagram.
+async fn read(transport: Arc<dyn AsyncDatagram>, shared: Arc<Shared>) {
+    let mut buf = vec![0u8; MAX_DATAGRAM];
+    loop {
+        let received = tokio::select! {
+            received = transport::recv_from(&*transport, &mut buf) => received,
+            _ = shared.repoll.notified() => continue,
+        };
+        match received {
+            Ok((len, from)) => shared.route(&buf[..len], from, Instant::now()),
+            Err(e) => {
+                for query in &shared.waiting.lock().unwrap().queries {
+                    let _ = query.tx.send(Err(io::Error::new(e.kind(), e.to_string())));
+                }
+                return;
+            },
+        }
+    }
+}
+
+impl Shared {
+    fn new() -> Self {
+        Shared {
+            waiting: Mutex::new(Waiting::default()),
+            repoll: Notify::new(),
+            #[cfg(feature = "socks5")]
+            relay: None,
+        }
+    }
+
+    /// Hands the datagram to the query whose reply it matches best, preferring ones still waiting.
+    fn route(&self, datagram: &[u8], from: SocketAddr, received: Instant) {
+        // relayed datagrams start with a header naming the server that sent them
+        #[cfg(feature = "socks5")]
+        let (datagram, from) = match self.relay {
+            Some(relay) if crate::utils::same_addr(from, relay) => match crate::socks5::unwrap(datagram) {
+                Ok((from, start)) => (&datagram[start..], from),
+                Err(_) => return,
+            },
+            Some(_) => return,
+            None => (datagram, from),
+        };
+        let mut waiting = self.waiting.lock().unwrap();
+        // the first registered of equally good matches
+        let query = waiting.queries.iter_mut().rev()
+            .filter(|query| crate::utils::same_addr(query.remote, from))
+            .max_by_key(|query| (query.matches(datagram), query.armed));
+        let Some(query) = query else {
+            trace_event!(debug, remote = %from, bytes = datagram.len(), "discarded datagram no query is waiting for");
+            return;
+        };
+        query.armed = false;
+        let _ = query.tx.send(Ok((datagram.to_vec(), received)));
+    }
+}
+
+impl Expected {
+    /// Waits for the next datagram routed to this query.
+    pub(crate) async fn recv(&mut self) -> io::Result<(Vec<u8>, Instant)> {
+        self.rx.recv().await.expect("the sender lives as long as the query is registered")
+    }
+
+    /// Marks the datagram last handed over as not this query's reply, so it's waiting again.
+    pub(crate) fn rearm(&self) {
+        let mut waiting = self.shared.waiting.lock().unwrap();
+        if let Some(query) = waiting.queries.iter_mut().find(|query| query.key == self.key) {
+            query.armed = true;
+        }
+    }
+}
+
+impl Drop for Expected {
+    fn drop(&mut self) {
+        self.shared.waiting.lock().unwrap().queries.retain(|query| query.key != self.key);
+    }
+}
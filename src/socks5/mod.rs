@@ -0,0 +1,130 @@
+//! Routes a [Client](crate::Client)'s datagrams through a SOCKS5 proxy (RFC 1928).
+//!
+//! Only available with the `socks5` feature. Only UDP ASSOCIATE is implemented, with either
+//! no authentication or username/password authentication (RFC 1929).
+
+use std::convert::TryInto;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use crate::{Error, Result};
+
+const VERSION: u8 = 0x05;
+const NO_AUTH: u8 = 0x00;
+const USERNAME_PASSWORD: u8 = 0x02;
+const USERNAME_PASSWORD_VERSION: u8 = 0x01;
+const UDP_ASSOCIATE: u8 = 0x03;
+const IPV4: u8 = 0x01;
+const IPV6: u8 = 0x04;
+const SUCCEEDED: u8 = 0x00;
+
+/// A UDP association with a SOCKS5 proxy.
+#[derive(Debug)]
+pub(crate) struct Relay {
+    /// The proxy ends the association once this connection closes.
+    _control: TcpStream,
+    /// Where datagrams are sent to be relayed.
+    pub(crate) addr: SocketAddr,
+}
+
+impl Relay {
+    /// Connects to the proxy, authenticates and asks it for a UDP relay.
+    pub(crate) async fn associate(proxy: SocketAddr, credentials: Option<&(String, String)>) -> Result<Self> {
+        let mut stream = TcpStream::connect(proxy).await?;
+        let method = if credentials.is_some() { USERNAME_PASSWORD } else { NO_AUTH };
+        stream.write_all(&[VERSION, 1, method]).await?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != VERSION {
+            return Err(Error::MalformedResponse(format!("SOCKS5 proxy answered with version {}", reply[0])));
+        }
+        if reply[1] != method {
+            return Err(io_error(ErrorKind::PermissionDenied, "SOCKS5 proxy accepted none of the offered auth methods"));
+        }
+        if let Some((username, password)) = credentials {
+            if username.len() > u8::MAX as usize || password.len() > u8::MAX as usize {
+                return Err(io_error(ErrorKind::InvalidInput, "SOCKS5 username and password can be at most 255 bytes"));
+            }
+            let mut request = vec![USERNAME_PASSWORD_VERSION, username.len() as u8];
+            request.extend_from_slice(username.as_bytes());
+            request.push(password.len() as u8);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).await?;
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != SUCCEEDED {
+                return Err(io_error(ErrorKind::PermissionDenied, "SOCKS5 proxy rejected the credentials"));
+            }
+        }
+        // the datagrams may come from any address, the proxy learns it from the first one
+        stream.write_all(&[VERSION, UDP_ASSOCIATE, 0x00, IPV4, 0, 0, 0, 0, 0, 0]).await?;
+        let mut head = [0u8; 4];
+        stream.read_exact(&mut head).await?;
+        if head[1] != SUCCEEDED {
+            return Err(io_error(ErrorKind::ConnectionRefused, &format!("SOCKS5 proxy refused the udp associate with reply {:#04x}", head[1])));
+        }
+        let mut addr = [0u8; 16];
+        let ip = match head[3] {
+            IPV4 => {
+                stream.read_exact(&mut addr[..4]).await?;
+                IpAddr::from([addr[0], addr[1], addr[2], addr[3]])
+            },
+            IPV6 => {
+                stream.read_exact(&mut addr).await?;
+                IpAddr::from(addr)
+            },
+            atyp => return Err(Error::MalformedResponse(format!("SOCKS5 proxy sent a relay address of type {}", atyp))),
+        };
+        let port = stream.read_u16().await?;
+        // an unspecified relay address means the proxy's own address
+        let ip = if ip.is_unspecified() { proxy.ip() } else { ip };
+        Ok(Relay { _control: stream, addr: SocketAddr::new(ip, port) })
+    }
+}
+
+/// Prepends the UDP request header addressing the datagram to `to`.
+pub(crate) fn wrap(to: SocketAddr, data: &[u8]) -> Vec<u8> {
+    // reserved and fragment number, fragmenting isn't supported
+    let mut buf = vec![0x00, 0x00, 0x00];
+    match to.ip() {
+        IpAddr::V4(ip) => {
+            buf.push(IPV4);
+            buf.extend_from_slice(&ip.octets());
+        },
+        IpAddr::V6(ip) => {
+            buf.push(IPV6);
+            buf.extend_from_slice(&ip.octets());
+        },
+    }
+    buf.extend_from_slice(&to.port().to_be_bytes());
+    buf.extend_from_slice(data);
+    buf
+}
+
+/// Reads the UDP request header off a relayed datagram, returning who sent it and where its data starts.
+pub(crate) fn unwrap(datagram: &[u8]) -> Result<(SocketAddr, usize)> {
+    let too_short = || Error::MalformedResponse("Relayed datagram is too short to hold a SOCKS5 header".to_string());
+    match datagram.get(2) {
+        Some(0x00) => {},
+        Some(_) => return Err(Error::MalformedResponse("Received a fragmented SOCKS5 datagram".to_string())),
+        None => return Err(too_short()),
+    }
+    let (ip, start) = match datagram.get(3) {
+        Some(&IPV4) => {
+            let ip: [u8; 4] = datagram.get(4..8).ok_or_else(too_short)?.try_into().unwrap();
+            (IpAddr::V4(Ipv4Addr::from(ip)), 8)
+        },
+        Some(&IPV6) => {
+            let ip: [u8; 16] = datagram.get(4..20).ok_or_else(too_short)?.try_into().unwrap();
+            (IpAddr::V6(Ipv6Addr::from(ip)), 20)
+        },
+        Some(_) => return Err(Error::MalformedResponse("Relayed datagram has an unsupported SOCKS5 address type".to_string())),
+        None => return Err(too_short()),
+    };
+    let port = datagram.get(start..start + 2).ok_or_else(too_short)?;
+    Ok((SocketAddr::new(ip, u16::from_be_bytes([port[0], port[1]])), start + 2))
+}
+
+fn io_error(kind: ErrorKind, message: &str) -> Error {
+    Error::Io(std::io::Error::new(kind, message))
+}
@@ -1,9 +1,121 @@
-use crate::Client;
-use std::io::Result;
+use crate::{Client, Result};
 use tokio::time::Instant;
 use tokio::io::AsyncWriteExt;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// Builds an unconnected pong datagram around the given server id string.
+fn pong(server_id: &str) -> Vec<u8> {
+    let mut buf = vec![0x1C];
+    buf.extend_from_slice(&[0x00; 8]);
+    buf.extend_from_slice(&[0x01; 8]);
+    buf.extend_from_slice(&crate::packet::OFFLINE_MESSAGE_DATA_ID);
+    buf.extend_from_slice(&(server_id.len() as u16).to_be_bytes());
+    buf.extend_from_slice(server_id.as_bytes());
+    buf
+}
+
+/// Builds an unconnected pong datagram that sends the server guid after the magic.
+fn pong_guid_after_magic(server_id: &str) -> Vec<u8> {
+    let mut buf = vec![0x1C];
+    buf.extend_from_slice(&[0x00; 8]);
+    buf.extend_from_slice(&crate::packet::OFFLINE_MESSAGE_DATA_ID);
+    buf.extend_from_slice(&[0x01; 8]);
+    buf.extend_from_slice(&(server_id.len() as u16).to_be_bytes());
+    buf.extend_from_slice(server_id.as_bytes());
+    buf
+}
+
+/// Spawns a local udp server answering every datagram it receives with `reply`.
+///
+/// Empty replies aren't sent, which simulates a lost packet.
+async fn mock_server<F>(reply: F) -> Result<std::net::SocketAddr>
+where F: FnMut(&[u8]) -> Vec<u8> + Send + 'static
+{
+    mock_server_at("127.0.0.1:0", reply).await
+}
+
+/// Same as [mock_server], listening on the given address.
+async fn mock_server_at<F>(bind: &str, mut reply: F) -> Result<std::net::SocketAddr>
+where F: FnMut(&[u8]) -> Vec<u8> + Send + 'static
+{
+    let server = UdpSocket::bind(bind).await?;
+    let addr = server.local_addr()?;
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1024];
+        while let Ok((len, from)) = server.recv_from(&mut buf).await {
+            let reply = reply(&buf[..len]);
+            if !reply.is_empty() {
+                let _ = server.send_to(&reply, from).await;
+            }
+        }
+    });
+    Ok(addr)
+}
+
+/// Builds a full stat response for the given session out of key value pairs and player names.
+fn full_stat(session: &[u8], kv: &[(&str, &str)], players: &[&str]) -> Vec<u8> {
+    let mut buf = vec![0x00];
+    buf.extend_from_slice(session);
+    buf.extend_from_slice(b"splitnum\0\x80\0");
+    for (key, value) in kv {
+        buf.extend_from_slice(key.as_bytes());
+        buf.push(0x00);
+        buf.extend_from_slice(value.as_bytes());
+        buf.push(0x00);
+    }
+    buf.extend_from_slice(b"\0\x01player_\0\0");
+    for player in players {
+        buf.extend_from_slice(player.as_bytes());
+        buf.push(0x00);
+    }
+    buf.push(0x00);
+    buf
+}
+
+/// The key value pairs of a full stat response from a typical PocketMine server.
+const POCKETMINE_KV: [(&str, &str); 12] = [
+    ("hostname", "A PocketMine Server"), ("gametype", "SMP"), ("game_id", "MINECRAFTPE"),
+    ("version", "v1.19.1"), ("server_engine", "PocketMine-MP"), ("plugins", "PocketMine-MP 4.0.0: Essentials 1.0"),
+    ("map", "world"), ("numplayers", "2"), ("maxplayers", "20"), ("whitelist", "off"),
+    ("hostip", "127.0.0.1"), ("hostport", "19132"),
+];
+
+/// Spawns a local GS3 server answering handshakes with a token and stat requests with `stat(session)`.
+async fn gs3_server<F>(stat: F) -> Result<std::net::SocketAddr>
+where F: Fn(&[u8]) -> Vec<u8> + Send + 'static
+{
+    mock_server(move |req| match req[2] {
+        0x09 => [&[0x09], &req[3..7], &b"9513307\0"[..]].concat(),
+        _ => stat(&req[3..7]),
+    }).await
+}
+
+/// A long query as returned by a typical PocketMine server.
+fn long_query_data() -> crate::model::LongQuery {
+    crate::model::LongQuery {
+        server_software: "PocketMine-MP".to_string(),
+        plugins: "PocketMine-MP 4.0.0: Essentials 1.0".to_string(),
+        version: "v1.19.1".to_string(),
+        whitelist: "off".to_string(),
+        players: vec!["Steve".to_string(), "Alex".to_string()],
+        player_count: 2,
+        max_players: 20,
+        game_name: "MINECRAFTPE".to_string(),
+        game_mode: "SMP".to_string(),
+        map_name: "world".to_string(),
+        host_name: "A PocketMine Server".to_string(),
+        host_ip: "127.0.0.1".to_string(),
+        host_port: 19132,
+        extra: crate::model::ExtraMap::new()
+    }
+}
+
+/// A pong captured from a vanilla bedrock dedicated server.
+const BDS_PONG: &str = "MCPE;Dedicated Server;527;1.19.1;3;10;13253860892328930865;Bedrock level;Survival;1;19132;19133;";
 
 #[tokio::test]
+#[ignore = "queries a live server"]
 async fn raknet_ping() -> Result<()> {
     let client = Client::new("dcfac.us.to:19132").await?;
     let start = Instant::now();
@@ -13,6 +125,7 @@ async fn raknet_ping() -> Result<()> {
 }
 
 #[tokio::test]
+#[ignore = "queries a live server"]
 async fn long_query() -> Result<()> {
     let client = Client::new("dcfac.us.to:19132").await?;
     let start = Instant::now();
@@ -24,16 +137,1511 @@ async fn long_query() -> Result<()> {
 #[tokio::test]
 async fn slice_index() -> Result<()> {
     let mut source: Vec<u8> = vec![0x01, 0x02];
-    source.write(&crate::packet::PLAYER_KEY).await?;
+    source.write_all(&crate::packet::PLAYER_KEY).await?;
     println!("index: {:?}", crate::utils::slice_index(source.as_slice(), &crate::packet::PLAYER_KEY));
     Ok(())
 }
 
 #[tokio::test]
+#[ignore = "queries a live server"]
 async fn short_query() -> Result<()> {
     let client = Client::new("dcfac.us.to:19132").await?;
     let start = Instant::now();
     let data = client.short_query().await?;
     println!("short finished in {}ms\n{:?}", start.elapsed().as_millis(), data);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn query_at() -> Result<()> {
+    let pong_addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let stat_addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &["Steve"])).await?;
+    let client = Client::new(pong_addr).await?;
+    assert_eq!(client.long_query_at(stat_addr).await?.players, ["Steve"]);
+    assert_eq!(*client.remote(), pong_addr);
+    assert_eq!(client.raknet_ping().await?.player_count, 3);
+    assert_eq!(client.raknet_ping_at(pong_addr).await?.player_count, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_at_concurrently() -> Result<()> {
+    let pongs = [
+        mock_server(|_| pong(&BDS_PONG.replace("Dedicated Server", "First"))).await?,
+        mock_server(|_| pong(&BDS_PONG.replace("Dedicated Server", "Second"))).await?,
+    ];
+    let stats = [
+        gs3_server(|session| full_stat(session, &[&[("hostname", "First")], &POCKETMINE_KV[1..]].concat(), &[])).await?,
+        gs3_server(|session| full_stat(session, &[&[("hostname", "Second")], &POCKETMINE_KV[1..]].concat(), &[])).await?,
+    ];
+    let client = std::sync::Arc::new(Client::new(pongs[0]).await?);
+    let mut queries = tokio::task::JoinSet::new();
+    for i in 0..20 {
+        let client = client.clone();
+        let (pong, stat) = (pongs[i % 2], stats[i % 2]);
+        queries.spawn(async move {
+            let motd = client.raknet_ping_at(pong).await?.motd.remove(0);
+            let host_name = client.long_query_at(stat).await?.host_name;
+            Ok::<_, crate::Error>((i, motd, host_name))
+        });
+    }
+    let expected = ["First", "Second"];
+    while let Some(query) = tokio::time::timeout(Duration::from_secs(1), queries.join_next()).await.expect("queries got stuck") {
+        let (i, motd, host_name) = query.unwrap()?;
+        assert_eq!((&motd[..], &host_name[..]), (expected[i % 2], expected[i % 2]));
+    }
+    Ok(())
+}
+#[tokio::test]
+async fn identify_as() -> Result<()> {
+    assert_eq!(crate::utils::fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    let mut first = Client::new("127.0.0.1:19132").await?;
+    let mut second = Client::new("127.0.0.1:19132").await?;
+    first.identify_as("monitor");
+    second.identify_as("monitor");
+    assert_eq!(first.client_id(), second.client_id());
+    assert_eq!(first.session_id(), second.session_id());
+    second.identify_as("other");
+    assert_ne!(first.client_id(), second.client_id());
+    Ok(())
+}
+
+#[test]
+fn motd_lines() -> Result<()> {
+    let data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(data.motd_lines(), ("Dedicated Server", Some("Bedrock level")));
+    assert_eq!((data.motd_line1(), data.motd_line2()), ("Dedicated Server", Some("Bedrock level")));
+    let mut data = crate::parse::parse_pong(&pong("MCPE;Old Server;100;1.0.0;0;20;1234"))?;
+    assert_eq!(data.motd_lines(), ("Old Server", None));
+    assert_eq!((data.motd_line1(), data.motd_line2()), ("Old Server", None));
+    data.motd.clear();
+    assert_eq!(data.motd_lines(), ("", None));
+    assert_eq!(data.to_string(), " (0/20)");
+    assert_eq!(crate::model::QueryData::motd(&data), "");
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancelled_query() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let client = Client::new(server.local_addr()?).await?;
+    let mut buf = [0u8; 64];
+    // The first ping is cancelled before the server gets to answer it.
+    assert!(tokio::time::timeout(Duration::from_millis(20), client.raknet_ping()).await.is_err());
+    let (_, from) = server.recv_from(&mut buf).await?;
+    server.send_to(&pong("MCPE;Stale;527;1.19.1;0;10;1"), from).await?;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    // The next ping must get its own response rather than the stale one.
+    let serve = async {
+        let (_, from) = server.recv_from(&mut buf).await?;
+        Ok(server.send_to(&pong("MCPE;Fresh;527;1.19.1;0;10;1"), from).await?)
+    };
+    let (data, _) = tokio::try_join!(client.raknet_ping(), serve)?;
+    assert_eq!(data.motd_lines().0, "Fresh");
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancelled_query_cached_token() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let client = Client::new(server.local_addr()?).await?;
+    let mut buf = [0u8; 64];
+    let stat = |session: &[u8], hostname: &'static str| {
+        let mut kv = POCKETMINE_KV;
+        kv[0] = ("hostname", hostname);
+        full_stat(session, &kv, &[])
+    };
+    let serve = |hostname: &'static str| {
+        let server = &server;
+        async move {
+            let mut buf = [0u8; 64];
+            let (len, from) = server.recv_from(&mut buf).await?;
+            let reply = match buf[2] {
+                0x09 => [&[0x09], &buf[3..7], &b"9513307\0"[..]].concat(),
+                _ => stat(&buf[3..7], hostname),
+            };
+            server.send_to(&reply, from).await?;
+            Ok::<_, crate::Error>(len)
+        }
+    };
+    // caches the token, the next queries send their stat request right away
+    tokio::try_join!(client.long_query(), async { serve("Fresh").await?; serve("Fresh").await })?;
+    assert!(tokio::time::timeout(Duration::from_millis(20), client.long_query()).await.is_err());
+    let (_, from) = server.recv_from(&mut buf).await?;
+    server.send_to(&stat(&buf[3..7], "Stale"), from).await?;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let (data, _) = tokio::try_join!(client.long_query(), serve("Fresh"))?;
+    assert_eq!(data.host_name, "Fresh");
+    Ok(())
+}
+
+#[tokio::test]
+async fn pipelined_stat() -> Result<()> {
+    let expired = std::time::Instant::now() - crate::packet::TOKEN_LIFETIME;
+    // only accepts the token it hands out
+    let addr = mock_server(|req| match req[2] {
+        0x09 => [&[0x09], &req[3..7], &b"9513307\0"[..]].concat(),
+        _ if req[7..11] == 9513307i32.to_be_bytes() => full_stat(&req[3..7], &POCKETMINE_KV, &[]),
+        _ => Vec::new(),
+    }).await?;
+    let client = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).pipeline(true).build(addr).await?;
+    // the expired token is still accepted
+    *client.token.lock().unwrap() = Some((1, 9513307, expired));
+    assert_eq!(client.long_query().await?.host_name, "A PocketMine Server");
+    // an outdated one isn't, the stat request is resent with the new token
+    *client.token.lock().unwrap() = Some((1, 1234, expired));
+    assert_eq!(client.long_query().await?.host_name, "A PocketMine Server");
+    assert!(client.token.lock().unwrap().is_some_and(|(_, token, at)| token == 9513307 && at > expired));
+    Ok(())
+}
+
+#[tokio::test]
+async fn late_reply_cached_token() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let client = Client::new(server.local_addr()?).await?;
+    let stat = |session: &[u8], hostname: &'static str| {
+        let mut kv = POCKETMINE_KV;
+        kv[0] = ("hostname", hostname);
+        full_stat(session, &kv, &[])
+    };
+    let mut buf = [0u8; 64];
+    let serve = async {
+        let mut buf = [0u8; 64];
+        for _ in 0..2 {
+            let (_, from) = server.recv_from(&mut buf).await?;
+            let reply = match buf[2] {
+                0x09 => [&[0x09], &buf[3..7], &b"9513307\0"[..]].concat(),
+                _ => stat(&buf[3..7], "Fresh"),
+            };
+            server.send_to(&reply, from).await?;
+        }
+        Ok::<_, crate::Error>(())
+    };
+    tokio::try_join!(client.long_query(), serve)?;
+    // the reply to a cancelled query with the cached token only arrives once the next one was sent
+    assert!(tokio::time::timeout(Duration::from_millis(20), client.long_query()).await.is_err());
+    server.recv_from(&mut buf).await?;
+    let late = stat(&buf[3..7], "Stale");
+    let serve = async {
+        let mut buf = [0u8; 64];
+        let (_, from) = server.recv_from(&mut buf).await?;
+        server.send_to(&late, from).await?;
+        server.send_to(&stat(&buf[3..7], "Fresh"), from).await?;
+        Ok::<_, crate::Error>(())
+    };
+    let (data, _) = tokio::try_join!(client.long_query(), serve)?;
+    assert_eq!(data.host_name, "Fresh");
+    Ok(())
+}
+
+#[test]
+fn compact_status() -> Result<()> {
+    let data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(data.compact_status(), crate::model::CompactStatus { online: true, players: 3, max: 10 });
+    let data = crate::parse::parse_pong(&pong("MCPE;Huge;527;1.19.1;70000;100000;1"))?;
+    assert_eq!(data.compact_status(), crate::model::CompactStatus { online: true, players: u16::MAX, max: u16::MAX });
+    Ok(())
+}
+
+#[test]
+fn fmt_latency() {
+    assert_eq!(crate::fmt_latency(&Duration::from_micros(12_700)), "12ms");
+    assert_eq!(crate::fmt_latency(&Duration::from_millis(1_240)), "1.2s");
+}
+
+#[tokio::test]
+async fn gen_challenge_token_raw() -> Result<()> {
+    let addr = mock_server(|req| [&[0x09], &req[3..7], &b"9513307\0"[..]].concat()).await?;
+    let (token, reply) = Client::new(addr).await?.gen_challenge_token_raw(0x01020304).await?;
+    assert_eq!(token, 9513307);
+    assert_eq!(reply, b"\x09\x01\x02\x03\x049513307\0");
+    let addr = mock_server(|_| b"\x09\0\0\0\0bogus\0".to_vec()).await?;
+    let err = Client::new(addr).await?.gen_challenge_token_raw(1).await.unwrap_err();
+    assert!(err.to_string().ends_with(&hex::encode(b"\x09\0\0\0\0bogus\0")));
+    Ok(())
+}
+
+#[test]
+fn major_minor() -> Result<()> {
+    let mut data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(data.major_minor(), Some((1, 19)));
+    for (version, expected) in [("1.20.15", Some((1, 20))), ("1.21-beta", Some((1, 21))), ("1.2", Some((1, 2))), ("1", None), ("", None)] {
+        data.game_version = version.to_string();
+        assert_eq!(data.major_minor(), expected, "{}", version);
+    }
+    Ok(())
+}
+
+#[test]
+fn completeness() {
+    let mut data = long_query_data();
+    assert_eq!(data.completeness(), 1.0);
+    data.plugins.clear();
+    data.players.clear();
+    assert_eq!(data.completeness(), 11.0 / 13.0);
+}
+
+#[tokio::test]
+async fn connect_port() -> Result<()> {
+    let addr = mock_server(|_| pong("MCPE;Proxy;527;1.19.1;0;10;1;Lobby;Survival;1;19140;19141;")).await?;
+    let data = Client::new(addr).await?.raknet_ping().await?;
+    assert_eq!((data.connect_port(), data.port_v6), (19140, Some(19141)));
+    let addr = mock_server(|_| pong("MCPE;Old Server;100;1.0.0;0;20;1234")).await?;
+    let data = Client::new(addr).await?.raknet_ping().await?;
+    assert_eq!((data.connect_port(), data.port_v6), (addr.port(), None));
+    Ok(())
+}
+
+#[test]
+fn slice_index_empty_needle() {
+    assert_eq!(crate::utils::slice_index(&[0x01u8, 0x02], &[]), None);
+}
+
+#[test]
+fn slice_index_short_buffer() {
+    assert_eq!(crate::utils::slice_index(&[0x01u8], &[0x01, 0x02]), None);
+    assert_eq!(crate::utils::slice_index(&[], &[0x01u8]), None);
+    // a truncated pong is an error rather than a panic
+    assert!(crate::parse::parse_pong(&[0x1C, 0x00, 0xff]).is_err());
+}
+
+#[test]
+fn query_data() -> Result<()> {
+    use crate::model::{Edition, QueryData};
+    fn summary(data: &dyn QueryData) -> (usize, usize, &str, Option<Edition>) {
+        (data.players(), data.max_players(), data.motd(), data.edition())
+    }
+    let pong = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(summary(&pong), (3, 10, "Dedicated Server", Some(Edition::Bedrock)));
+    assert_eq!(summary(&long_query_data()), (2, 20, "A PocketMine Server", Some(Edition::Bedrock)));
+    Ok(())
+}
+
+#[test]
+fn pong_field_order() -> Result<()> {
+    let guid_first = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    let guid_after = crate::parse::parse_pong(&pong_guid_after_magic(BDS_PONG))?;
+    assert_eq!(guid_first.motd, guid_after.motd);
+    assert_eq!(guid_after.server_uid, "13253860892328930865");
+    assert_eq!((guid_first.server_guid, guid_after.server_guid), (0x0101010101010101, 0x0101010101010101));
+    assert_eq!(guid_first.ping_time, 0);
+    assert!(crate::parse::parse_pong(&[0x1C; 40]).is_err());
+    Ok(())
+}
+
+#[test]
+fn pong_validation() {
+    use crate::{Error, parse::parse_pong};
+    let mut stray = pong(BDS_PONG);
+    stray[0] = 0x05;
+    assert!(matches!(parse_pong(&stray), Err(Error::UnexpectedPacket { expected: 0x1C, got: 0x05 })));
+    // the magic has to be where a pong puts it, not just anywhere in the datagram
+    let mut shifted = pong(BDS_PONG);
+    shifted.insert(1, 0x00);
+    assert!(matches!(parse_pong(&shifted), Err(Error::MalformedResponse(_))));
+    assert!(matches!(parse_pong(&[]), Err(Error::MalformedResponse(_))));
+    assert!(matches!(parse_pong(&pong("MCPE;Broken;527;1.19.1")), Err(Error::MalformedResponse(_))));
+    assert!(matches!(parse_pong(&pong("MCPE;Modded;;1.19.1;0;10;1")), Err(Error::ParseField { field: "protocol_version" })));
+    assert!(matches!(parse_pong(&pong("MCPE;Modded;527;1.19.1;0;lots;1")), Err(Error::ParseField { field: "max_player_count" })));
+    // a second motd line without a gamemode
+    assert_eq!(parse_pong(&pong("MCPE;Server;527;1.19.1;0;10;1;World")).unwrap().game_mode, None);
+}
+
+#[tokio::test]
+async fn stream_into() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let client = Client::new(addr).await?;
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<crate::model::RakNetPong>>(1);
+    let receive = async move {
+        for _ in 0..2 {
+            assert_eq!(rx.recv().await.unwrap().unwrap().player_count, 3);
+        }
+    };
+    // stream_into has to return once the receiver is dropped
+    tokio::join!(client.stream_into(tx, Duration::from_millis(5)), receive);
+    Ok(())
+}
+
+#[test]
+fn game_mode_name() -> Result<()> {
+    let mut data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(data.game_mode_integer, Some(1));
+    assert_eq!(data.game_mode_name(), Some("Survival"));
+    // only the number of the same pong
+    data.game_mode = None;
+    assert_eq!(data.game_mode_name(), Some("Survival"));
+    data.game_mode_integer = Some(2);
+    assert_eq!(data.game_mode_name(), Some("Creative"));
+    data.game_mode_integer = Some(3);
+    assert_eq!(data.game_mode_name(), Some("Adventure"));
+    data.game_mode_integer = Some(0);
+    assert_eq!(data.game_mode_name(), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn long_query_require() -> Result<()> {
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &["Steve", "Alex"])).await?;
+    let client = Client::new(addr).await?;
+    assert_eq!(client.long_query_require(&["version"]).await?.players, ["Steve", "Alex"]);
+    let err = client.long_query_require(&["tps", "version", "motd"]).await.unwrap_err();
+    assert_eq!(err.to_string(), "Malformed response: Server response is missing required keys: tps, motd");
+    Ok(())
+}
+
+#[tokio::test]
+async fn raknet_reply_to_gs3() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let err = Client::new(addr).await?.short_query().await.unwrap_err();
+    assert!(err.to_string().ends_with("try raknet_ping"), "{}", err);
+    Ok(())
+}
+
+#[cfg(feature = "pcap")]
+#[tokio::test]
+async fn pcap_capture() -> Result<()> {
+    #[derive(Clone, Default)]
+    struct Capture(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+    impl std::io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let capture = Capture::default();
+    let mut client = Client::new(addr).await?;
+    client.set_pcap_writer(capture.clone())?;
+    client.raknet_ping().await?;
+    let bytes = capture.0.lock().unwrap().clone();
+    assert_eq!(bytes[..4], [0xd4, 0xc3, 0xb2, 0xa1]);
+    // the ping and the pong, each with a record header, an ipv4 header and a udp header
+    let ping_len = 16 + 20 + 8 + 33;
+    let pong_len = 16 + 20 + 8 + pong(BDS_PONG).len();
+    assert_eq!(bytes.len(), 24 + ping_len + pong_len);
+    // the pong was sent from the mock server's port
+    assert_eq!(bytes[24 + ping_len + 16 + 20..][..2], addr.port().to_be_bytes());
+    Ok(())
+}
+
+#[tokio::test]
+async fn gen_challenge_token_timed() -> Result<()> {
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &[])).await?;
+    let (token, rtt) = Client::new(addr).await?.gen_challenge_token_timed(1).await?;
+    assert_eq!(token, 9513307);
+    assert!(rtt < Duration::from_secs(1));
+    Ok(())
+}
+
+#[test]
+fn split_full_stat() -> Result<()> {
+    use crate::parse::{split_full_stat, FULL_STAT_HEADER_LEN};
+    let kv = [("hostname", "A Server"), ("numplayers", "2")];
+    let stat = full_stat(&[0; 4], &kv, &["Steve", "Alex"]);
+    let body = &stat[FULL_STAT_HEADER_LEN..];
+    assert_eq!(split_full_stat(body)?, (&b"hostname\0A Server\0numplayers\x002\0"[..], Some(&b"Steve\0Alex"[..])));
+    // trailing padding after the player list is ignored
+    let padded = [body, &[0x00; 3]].concat();
+    assert_eq!(split_full_stat(&padded)?, split_full_stat(body)?);
+    // an empty player list is just the terminating empty name
+    let stat = full_stat(&[0; 4], &kv, &[]);
+    assert_eq!(split_full_stat(&stat[FULL_STAT_HEADER_LEN..])?.1, Some(&b""[..]));
+    // or nothing at all after the splitter
+    assert_eq!(split_full_stat(&stat[FULL_STAT_HEADER_LEN..stat.len() - 1])?.1, Some(&b""[..]));
+    // without the splitter everything is key value data
+    assert_eq!(split_full_stat(b"hostname\0A Server\0")?, (&b"hostname\0A Server\0"[..], None));
+    // cut off in the middle of the player list
+    assert!(split_full_stat(&body[..body.len() - 4]).is_err());
+    assert!(split_full_stat(&body[..body.len() - 1]).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn long_query_no_players() -> Result<()> {
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &[])).await?;
+    assert!(Client::new(addr).await?.long_query().await?.players.is_empty());
+    // some servers end the response right after the player splitter
+    let addr = gs3_server(|session| {
+        let mut stat = full_stat(session, &POCKETMINE_KV, &[]);
+        stat.pop();
+        stat
+    }).await?;
+    assert!(Client::new(addr).await?.long_query().await?.players.is_empty());
+    Ok(())
+}
+
+#[test]
+fn software_version() {
+    let mut data = long_query_data();
+    assert_eq!(data.software_version(), "PocketMine-MP v1.19.1");
+    data.server_software.clear();
+    assert_eq!(data.software_version(), "v1.19.1");
+}
+
+#[test]
+fn host_mismatch() {
+    use crate::model::AddrMismatch;
+    let data = long_query_data();
+    assert_eq!(data.host_mismatch("127.0.0.1:19132".parse().unwrap()), None);
+    assert_eq!(data.host_mismatch("127.0.0.1:19133".parse().unwrap()), Some(AddrMismatch::Port));
+    assert_eq!(data.host_mismatch("203.0.113.7:19133".parse().unwrap()), Some(AddrMismatch::Both));
+}
+
+#[tokio::test]
+async fn long_query_partial_kv() -> Result<()> {
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV[..4], &[])).await?;
+    let err = Client::new(addr).await?.long_query().await.unwrap_err();
+    assert!(matches!(err, crate::Error::MissingField("server_engine")), "{}", err);
+    let mut kv = POCKETMINE_KV;
+    kv[7].1 = "?";
+    let addr = gs3_server(move |session| full_stat(session, &kv, &[])).await?;
+    let err = Client::new(addr).await?.long_query().await.unwrap_err();
+    assert!(matches!(err, crate::Error::ParseField { field: "player_count" }), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn parse_count() {
+    use crate::utils::parse_count;
+    assert_eq!(parse_count("12", "players").unwrap(), 12);
+    assert_eq!(parse_count("", "players").unwrap(), 0);
+    assert!(matches!(parse_count("?", "max_players"), Err(crate::Error::ParseField { field: "max_players" })));
+}
+
+#[test]
+fn motd_spans() {
+    use crate::model::MotdSpan;
+    let data = crate::parse::parse_pong(&pong("MCPE;§l§6Gold §rServer;527;1.19.1;0;10;1;§oWorld§x;Survival")).unwrap();
+    assert_eq!(data.plain_motd(), "Gold Server\nWorld§x");
+    assert_eq!(data.motd_spans(), [
+        MotdSpan { text: "Gold ".to_string(), color: Some('6'), ..MotdSpan::default() },
+        MotdSpan { text: "Server\n".to_string(), ..MotdSpan::default() },
+        MotdSpan { text: "World§x".to_string(), italic: true, ..MotdSpan::default() },
+    ]);
+    assert_eq!(MotdSpan::strip("§cRed§L bold"), "Red bold");
+}
+
+#[tokio::test]
+async fn query_timed() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let (data, rtt) = Client::new(addr).await?.raknet_ping_timed().await?;
+    assert_eq!(data.remote, Some(addr));
+    assert!(rtt < Duration::from_secs(1));
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &["Steve"])).await?;
+    let (data, rtt) = Client::new(addr).await?.long_query_timed().await?;
+    assert_eq!(data.players, ["Steve"]);
+    assert!(rtt < Duration::from_secs(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_builder() -> Result<()> {
+    let mut attempts = 0;
+    // the first ping is lost, the retry gets answered
+    let addr = mock_server(move |_| {
+        attempts += 1;
+        if attempts == 1 { Vec::new() } else { pong(BDS_PONG) }
+    }).await?;
+    let client = crate::ClientBuilder::new()
+        .bind("127.0.0.1:0".parse().unwrap())
+        .timeout(Duration::from_millis(100))
+        .retries(1)
+        .build(addr).await?;
+    assert!(client.router.local_addr()?.ip().is_loopback());
+    assert_eq!(client.raknet_ping().await?.remote, Some(addr));
+    let silent = mock_server(|_| Vec::new()).await?;
+    let client = crate::ClientBuilder::new().timeout(Duration::from_millis(20)).build(silent).await?;
+    assert!(matches!(client.raknet_ping().await, Err(crate::Error::Timeout)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn retry_backoff() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    let sessions = Arc::new(Mutex::new(Vec::new()));
+    let seen = sessions.clone();
+    // a server that never answers the stat request
+    let addr = mock_server(move |req| match req[2] {
+        0x09 => {
+            seen.lock().unwrap().push(req[3..7].to_vec());
+            [&[0x09], &req[3..7], &b"9513307\0"[..]].concat()
+        },
+        _ => Vec::new(),
+    }).await?;
+    let client = crate::ClientBuilder::new()
+        .timeout(Duration::from_millis(10))
+        .retries(2)
+        .backoff(Duration::from_millis(40))
+        .jitter(false)
+        .build(addr).await?;
+    let start = Instant::now();
+    assert!(matches!(client.short_query().await, Err(crate::Error::Timeout)));
+    // waited 40ms before the first retry and 80ms before the second
+    assert!(start.elapsed() >= Duration::from_millis(120));
+    let sessions = sessions.lock().unwrap();
+    assert_eq!(sessions.len(), 3);
+    assert_ne!(sessions[0], sessions[1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn map_remote() -> Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let calls = std::sync::Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    // nothing listens on port 1, every query is redirected to the server
+    let client = crate::ClientBuilder::new()
+        .timeout(Duration::from_secs(1))
+        .map_remote(move |remote| {
+            assert_eq!(remote.port(), 1);
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(addr)
+        })
+        .build("127.0.0.1:1").await?;
+    let calls_before = calls.load(Ordering::SeqCst);
+    assert_eq!(client.raknet_ping().await?.remote, Some(addr));
+    assert_eq!(client.raknet_ping().await?.remote, Some(addr));
+    assert_eq!(calls.load(Ordering::SeqCst) - calls_before, 2);
+    let client = crate::ClientBuilder::new()
+        .map_remote(|_| Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "not on the allow list").into()))
+        .build(addr).await?;
+    assert!(matches!(client.raknet_ping().await, Err(crate::Error::Io(e)) if e.kind() == std::io::ErrorKind::PermissionDenied));
+    Ok(())
+}
+
+#[tokio::test]
+async fn retry_jitter() -> Result<()> {
+    let backoff = Duration::from_millis(40);
+    let client = crate::ClientBuilder::new().build("127.0.0.1:19132").await?;
+    let delays: Vec<_> = (0..100).map(|_| client.retry_delay(backoff)).collect();
+    assert!(delays.iter().all(|delay| *delay <= backoff));
+    assert!(delays.iter().any(|delay| *delay != delays[0]));
+    let client = crate::ClientBuilder::new().jitter(false).build("127.0.0.1:19132").await?;
+    assert_eq!(client.retry_delay(backoff), backoff);
+    Ok(())
+}
+
+#[tokio::test]
+async fn token_cache() -> Result<()> {
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+    let handshakes = Arc::new(AtomicUsize::new(0));
+    let counter = handshakes.clone();
+    let addr = mock_server(move |req| match req[2] {
+        0x09 => {
+            counter.fetch_add(1, Ordering::SeqCst);
+            [&[0x09], &req[3..7], &b"9513307\0"[..]].concat()
+        },
+        _ => full_stat(&req[3..7], &POCKETMINE_KV, &[]),
+    }).await?;
+    let mut client = Client::new(addr).await?;
+    client.long_query().await?;
+    client.long_query().await?;
+    assert_eq!(handshakes.load(Ordering::SeqCst), 1);
+    client.clear_token_cache();
+    client.long_query().await?;
+    assert_eq!(handshakes.load(Ordering::SeqCst), 2);
+    // vanilla servers echo the session of the handshake, not the stat request's
+    let handshake = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = mock_server(move |req| match req[2] {
+        0x09 => {
+            *handshake.lock().unwrap() = req[3..7].to_vec();
+            [&[0x09], &req[3..7], &b"9513307\0"[..]].concat()
+        },
+        _ => full_stat(&handshake.lock().unwrap(), &POCKETMINE_KV, &[]),
+    }).await?;
+    let client = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).build(addr).await?;
+    client.long_query().await?;
+    client.long_query().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn long_query_extra() -> Result<()> {
+    let kv = [&POCKETMINE_KV[..], &[("online_mode", "true"), ("bukkit_version", "1.19-R0.1")]].concat();
+    let addr = gs3_server(move |session| full_stat(session, &kv, &[])).await?;
+    let data = Client::new(addr).await?.long_query().await?;
+    assert_eq!(data.extra.len(), 2);
+    assert_eq!(data.extra["bukkit_version"], "1.19-R0.1");
+    Ok(())
+}
+
+#[tokio::test]
+async fn ping_many() -> Result<()> {
+    let mut addrs = Vec::new();
+    for motd in ["First", "Second", "Third"] {
+        let server_id = format!("MCPE;{};527;1.19.1;0;10;1", motd);
+        addrs.push(mock_server(move |_| pong(&server_id)).await?);
+    }
+    addrs.insert(1, mock_server(|_| Vec::new()).await?);
+    let builder = crate::ClientBuilder::new().timeout(Duration::from_millis(50));
+    let results = builder.ping_many(&addrs, 2).await;
+    assert_eq!(results.iter().map(|(addr, _)| *addr).collect::<Vec<_>>(), addrs);
+    assert!(matches!(results[1].1, Err(crate::Error::Timeout)));
+    let motds: Vec<_> = results.iter().filter_map(|(_, pong)| pong.as_ref().ok()).map(|pong| pong.motd_lines().0).collect();
+    assert_eq!(motds, ["First", "Second", "Third"]);
+    Ok(())
+}
+
+#[test]
+fn player_names() {
+    use crate::parse::player_names;
+    assert_eq!(player_names(b"Steve\0Alex", usize::MAX), ["Steve", "Alex"]);
+    assert_eq!(player_names(b"Steve\0\0\x01\x02\0 \0Alex\0\xff\xfe", usize::MAX), ["Steve", "Alex"]);
+    assert!(player_names(b"", usize::MAX).is_empty());
+    // skipped entries don't count towards the limit
+    assert_eq!(player_names(b"\x01\0Steve\0Alex\0Notch", 2), ["Steve", "Alex"]);
+}
+
+#[tokio::test]
+async fn query_with_session() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    let sessions = Arc::new(Mutex::new(Vec::new()));
+    let seen = sessions.clone();
+    let addr = gs3_server(move |session| {
+        seen.lock().unwrap().push(session.to_vec());
+        full_stat(session, &POCKETMINE_KV, &[])
+    }).await?;
+    let client = Client::new(addr).await?;
+    client.long_query_with_session(0x7172_7374).await?;
+    client.long_query_with_session(0x7172_7374).await?;
+    assert_eq!(*sessions.lock().unwrap(), [[0x01, 0x02, 0x03, 0x04], [0x01, 0x02, 0x03, 0x04]]);
+    Ok(())
+}
+
+#[test]
+fn version_name() -> Result<()> {
+    let mut data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(data.version_name(), Some("1.19.0"));
+    data.protocol_version = 622;
+    assert_eq!(data.version_name(), Some("1.20.40"));
+    data.protocol_version = 1;
+    assert_eq!(data.version_name(), None);
+    Ok(())
+}
+
+#[cfg(feature = "blocking")]
+#[tokio::test]
+async fn blocking_client() -> Result<()> {
+    let pong_addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let stat_addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &["Steve"])).await?;
+    let silent = mock_server(|_| Vec::new()).await?;
+    let v6_addr = mock_server_at("[::1]:0", |_| pong(BDS_PONG)).await?;
+    tokio::task::spawn_blocking(move || {
+        let mut client = crate::blocking::Client::new(pong_addr)?;
+        assert_eq!(client.raknet_ping()?.remote, Some(pong_addr));
+        client.set_remote(stat_addr);
+        assert_eq!(client.long_query()?.players, ["Steve"]);
+        let v6 = crate::blocking::Client::new(v6_addr)?;
+        assert_eq!(v6.raknet_ping()?.remote, Some(v6_addr));
+        client.set_remote(silent);
+        client.set_timeout(Some(Duration::from_millis(20)))?;
+        assert!(matches!(client.raknet_ping(), Err(crate::Error::Timeout)));
+        Ok(())
+    }).await.unwrap()
+}
+
+#[cfg(feature = "blocking")]
+#[tokio::test]
+async fn blocking_discarded_responses() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let addr = server.local_addr()?;
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1024];
+        while let Ok((len, from)) = server.recv_from(&mut buf).await {
+            let req = &buf[..len];
+            let _ = match req.first() {
+                Some(0x01) => {
+                    // an oversized datagram ahead of the reply is skipped
+                    let _ = server.send_to(&[0x1C; 2048], from).await;
+                    server.send_to(&pong(BDS_PONG), from).await
+                },
+                _ if req[2] == 0x09 => server.send_to(&[&[0x09], &req[3..7], &b"9513307\0"[..]].concat(), from).await,
+                _ => {
+                    // the late response to an earlier query arrives right before the real one
+                    let _ = server.send_to(&full_stat(&[0x0F, 0x0F, 0x0F, 0x0F], &[("hostname", "Stale")], &[]), from).await;
+                    server.send_to(&full_stat(&req[3..7], &POCKETMINE_KV, &[]), from).await
+                },
+            };
+        }
+    });
+    tokio::task::spawn_blocking(move || {
+        let mut client = crate::blocking::Client::new(addr)?;
+        client.set_timeout(Some(Duration::from_secs(1)))?;
+        assert_eq!(client.long_query()?.host_name, "A PocketMine Server");
+        client.set_max_response(pong(BDS_PONG).len());
+        assert_eq!(client.raknet_ping()?.player_count, 3);
+        client.set_max_response(pong(BDS_PONG).len() - 1);
+        client.set_timeout(Some(Duration::from_millis(100)))?;
+        assert!(matches!(client.raknet_ping(), Err(crate::Error::Timeout)));
+        Ok(())
+    }).await.unwrap()
+}
+
+#[tokio::test]
+async fn ping_stream() -> Result<()> {
+    use tokio_stream::StreamExt;
+    let mut attempts = 0;
+    // the second ping is lost
+    let addr = mock_server(move |_| {
+        attempts += 1;
+        if attempts == 2 { Vec::new() } else { pong(BDS_PONG) }
+    }).await?;
+    let client = crate::ClientBuilder::new().timeout(Duration::from_millis(20)).build(addr).await?;
+    let results: Vec<_> = client.ping_stream(Duration::from_millis(5)).take(3).collect().await;
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(crate::Error::Timeout)));
+    assert_eq!(results[2].as_ref().unwrap().player_count, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn resolve() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let client = Client::resolve(format!("localhost:{}", addr.port())).await?;
+    assert_eq!(client.resolved_addr().port(), addr.port());
+    assert!(client.resolved_addr().ip().is_loopback());
+    assert!(Client::resolve("").await.is_err());
+    Ok(())
+}
+
+#[cfg(feature = "java")]
+#[tokio::test]
+async fn java_status() -> Result<()> {
+    use tokio::io::AsyncReadExt;
+    let json = r#"{"version":{"name":"1.20.4","protocol":765},"players":{"max":20,"online":1,"sample":[{"name":"Steve","id":"8667ba71-b85a-4004-af54-457a9734eed7"}]},"description":{"text":"A ","extra":[{"text":"§aMinecraft"}," Server"]}}"#;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        // the handshake, ending with the status state, then the empty status request
+        let mut handshake = vec![0u8; stream.read_u8().await.unwrap() as usize];
+        stream.read_exact(&mut handshake).await.unwrap();
+        assert_eq!((handshake[0], handshake[handshake.len() - 1]), (0x00, 0x01));
+        // the host as given, after the packet id, the protocol and its length
+        assert_eq!(&handshake[7..16], b"127.0.0.1");
+        let mut request = [0u8; 2];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(request, [0x01, 0x00]);
+        // the json is between 128 and 16383 bytes long, so both lengths are two byte VarInts
+        let mut packet = vec![0x00, json.len() as u8 | 0x80, (json.len() >> 7) as u8];
+        packet.extend_from_slice(json.as_bytes());
+        let mut response = vec![packet.len() as u8 | 0x80, (packet.len() >> 7) as u8];
+        response.extend(packet);
+        stream.write_all(&response).await.unwrap();
+    });
+    let status = Client::new(addr.to_string()).await?.java_status().await?;
+    assert_eq!(status.version.protocol, 765);
+    assert_eq!((status.players.online, status.players.max), (1, 20));
+    assert_eq!(status.players.sample[0].name, "Steve");
+    assert_eq!(status.description, "A §aMinecraft Server");
+    assert!(status.favicon.is_none());
+    // an old server sending its motd as a plain string
+    let json = br#"{"version":{"name":"1.8","protocol":47},"players":{"max":1,"online":0},"description":"Hi"}"#;
+    let packet = [&[0x00, json.len() as u8][..], json].concat();
+    let mut status = crate::java::parse_status(&packet)?;
+    assert_eq!(status.description, "Hi");
+    assert_eq!(status.favicon_png(), None);
+    status.favicon = Some("data:image/png;base64,iVBORw0K\nGgo=".to_string());
+    assert_eq!(status.favicon_png().unwrap(), b"\x89PNG\r\n\x1a\n");
+    // too short to have an IHDR chunk
+    assert_eq!(status.favicon_dimensions(), None);
+    use base64::Engine;
+    let png = [&b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"[..], &64u32.to_be_bytes(), &32u32.to_be_bytes(), b"\x08\x06\0\0\0"].concat();
+    status.favicon = Some(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(&png)));
+    assert_eq!(status.favicon_dimensions(), Some((64, 32)));
+    use crate::Host;
+    assert_eq!("play.example.com:25565".host(), "play.example.com");
+    assert_eq!("[::1]:25565".to_string().host(), "::1");
+    assert_eq!(("play.example.com", 25565).host(), "play.example.com");
+    assert_eq!(addr.host(), "127.0.0.1");
+    status.favicon = Some("data:image/jpeg;base64,iVBORw0KGgo=".to_string());
+    assert_eq!(status.favicon_png(), None);
+    Ok(())
+}
+
+#[test]
+fn display() -> Result<()> {
+    let data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(data.to_string(), "Dedicated Server (3/10) - Bedrock level");
+    let data = crate::parse::parse_pong(&pong("MCPE;§bOld Server;100;1.0.0;0;20;1234"))?;
+    assert_eq!(data.to_string(), "Old Server (0/20)");
+    assert_eq!(long_query_data().to_string(), "A PocketMine Server (2/20) - world\nPocketMine-MP v1.19.1");
+    let data = crate::model::ShortQuery {
+        motd: "§aLobby".to_string(), gametype: "SMP".to_string(), map: "world".to_string(),
+        players: 1, max_players: 10, host_port: 19132, host_ip: "127.0.0.1".to_string(),
+    };
+    assert_eq!(data.to_string(), "Lobby (1/10) - world");
+    Ok(())
+}
+
+#[tokio::test]
+async fn player_count() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    assert_eq!(Client::new(addr).await?.player_count().await?, (3, 10));
+    Ok(())
+}
+
+#[test]
+fn read_nulltermed_str() -> Result<()> {
+    use crate::utils::read_nulltermed_str;
+    let mut buf = &b"motd\0trunc"[..];
+    assert_eq!(read_nulltermed_str(&mut buf), "motd");
+    assert_eq!(read_nulltermed_str(&mut buf), "trunc");
+    assert_eq!(read_nulltermed_str(&mut buf), "");
+    // a short stat cut off before the host port is an error, not a panic
+    assert!(crate::parse::parse_short_stat(b"\0\x01\x02\x03\x04motd\0SMP").is_err());
+    assert!(crate::parse::parse_short_stat(b"\0\x01").is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn socket_buffer_sizes() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let builder = crate::ClientBuilder::new().recv_buffer_size(1 << 20).send_buffer_size(1 << 16);
+    let socket = builder.bind_socket("127.0.0.1:0".parse().unwrap())?;
+    // linux doubles the requested size for bookkeeping, other platforms may round it
+    assert!(socket2::SockRef::from(&socket).recv_buffer_size()? >= 1 << 16);
+    let client = builder.build(addr).await?;
+    assert_eq!(client.raknet_ping().await?.player_count, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_trait() -> Result<()> {
+    use crate::Query;
+    use crate::model::{LongQuery, RakNetPong, ShortQuery};
+    async fn motd(server: &impl Query) -> Result<String> {
+        Ok(server.raknet_ping().await?.motd_lines().0.to_string())
+    }
+    struct Fake;
+    impl Query for Fake {
+        async fn raknet_ping(&self) -> Result<RakNetPong> {
+            crate::parse::parse_pong(&pong("MCPE;Fake;527;1.19.1;0;10;1"))
+        }
+        async fn short_query(&self) -> Result<ShortQuery> {
+            Err(crate::Error::Timeout)
+        }
+        async fn long_query(&self) -> Result<LongQuery> {
+            Ok(long_query_data())
+        }
+    }
+    assert_eq!(motd(&Fake).await?, "Fake");
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    assert_eq!(motd(&Client::new(addr).await?).await?, "Dedicated Server");
+    Ok(())
+}
+
+#[test]
+fn pong_flags() -> Result<()> {
+    let data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!((data.nintendo_limited, data.editor_mode), (None, None));
+    let data = crate::parse::parse_pong(&pong("MCPE;Server;685;1.21.0;0;10;1;World;Survival;1;19132;19133;1;0;"))?;
+    assert_eq!((data.nintendo_limited, data.editor_mode), (Some(true), Some(false)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn ping_with_client_id() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    let ids = Arc::new(Mutex::new(Vec::new()));
+    let seen = ids.clone();
+    let addr = mock_server(move |req| {
+        seen.lock().unwrap().push(req[25..33].to_vec());
+        pong(BDS_PONG)
+    }).await?;
+    let client = Client::new(addr).await?;
+    client.raknet_ping_with_client_id(0x0102_0304_0506_0708).await?;
+    client.raknet_ping_with_client_id(0x0102_0304_0506_0708).await?;
+    assert_eq!(*ids.lock().unwrap(), [[1, 2, 3, 4, 5, 6, 7, 8], [1, 2, 3, 4, 5, 6, 7, 8]]);
+    Ok(())
+}
+
+#[test]
+fn short_handshake_reply() {
+    for reply in [&b"\x09"[..], b"\x09\0\0\0\0", b"\x09\0\0\0\0\0"] {
+        let err = crate::parse::parse_challenge_token(reply).unwrap_err();
+        assert!(matches!(err, crate::Error::MalformedResponse(_)), "{:?}", err);
+    }
+}
+
+#[tokio::test]
+async fn full_profile() -> Result<()> {
+    let addr = mock_server(|req| match (req[0], req[2]) {
+        (0x01, _) => pong(BDS_PONG),
+        (_, 0x09) => [&[0x09], &req[3..7], &b"9513307\0"[..]].concat(),
+        _ => full_stat(&req[3..7], &POCKETMINE_KV, &["Steve", "Alex"]),
+    }).await?;
+    let profile = Client::new(addr).await?.full_profile().await?;
+    assert_eq!(profile.motd, ["Dedicated Server", "Bedrock level"]);
+    assert_eq!(profile.host_name, "A PocketMine Server");
+    assert_eq!((profile.player_count, profile.max_players), (2, 20));
+    assert_eq!(profile.players, ["Steve", "Alex"]);
+    assert_eq!(profile.game_version, "1.19.1");
+    assert_eq!(profile.game_mode.as_deref(), Some("Survival"));
+    assert_eq!((profile.port, profile.port_v6), (19132, Some(19133)));
+    assert_eq!(profile.server_software, "PocketMine-MP");
+    assert_eq!(profile.remote, Some(addr));
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_clone() -> Result<()> {
+    // answers with the client id's last byte as the motd, echoing the ping's timestamp like servers do
+    let addr = mock_server(|req| {
+        let mut reply = pong(&BDS_PONG.replace("Dedicated Server", &req[32].to_string()));
+        reply[1..9].copy_from_slice(&req[1..9]);
+        reply
+    }).await?;
+    let client = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).build(addr).await?;
+    *client.token.lock().unwrap() = Some((1, 2, std::time::Instant::now()));
+    let clone = client.clone();
+    assert_eq!(clone.router.local_addr()?, client.router.local_addr()?);
+    assert_eq!(clone.timeout, Some(Duration::from_secs(1)));
+    assert!(clone.token.lock().unwrap().is_none());
+    let task = tokio::spawn(async move {
+        tokio::join!(clone.raknet_ping_with_client_id(1), clone.raknet_ping_with_client_id(2))
+    });
+    let (own, clones) = tokio::join!(client.raknet_ping_with_client_id(3), task);
+    let (first, second) = clones.unwrap();
+    assert_eq!([&first?.motd[0], &second?.motd[0], &own?.motd[0]], ["1", "2", "3"]);
+    Ok(())
+}
+
+#[test]
+fn is_whitelisted() {
+    let mut data = long_query_data();
+    assert_eq!(data.is_whitelisted(), Some(false));
+    for (whitelist, expected) in [("On", Some(true)), ("true", Some(true)), ("FALSE", Some(false)), ("", None), ("maybe", None)] {
+        data.whitelist = whitelist.to_string();
+        assert_eq!(data.is_whitelisted(), expected, "{}", whitelist);
+    }
+}
+
+#[test]
+fn security_flags() {
+    use crate::model::SecurityFlags;
+    let mut data = long_query_data();
+    assert_eq!(data.security_flags(), SecurityFlags { whitelist: Some(false), online_mode: None });
+    data.whitelist = "on".to_string();
+    data.extra.insert("Online-Mode".to_string(), "true".to_string());
+    assert_eq!(data.security_flags(), SecurityFlags { whitelist: Some(true), online_mode: Some(true) });
+    data.extra.clear();
+    data.extra.insert("onlinemode".to_string(), "off".to_string());
+    assert_eq!(data.security_flags().online_mode, Some(false));
+}
+
+#[test]
+fn plugin_list() {
+    use crate::model::Plugin;
+    let plugin = |name: &str, version: Option<&str>| Plugin { name: name.to_string(), version: version.map(str::to_string) };
+    let mut data = long_query_data();
+    assert_eq!(data.plugin_list(), [plugin("Essentials", Some("1.0"))]);
+    data.plugins = "CraftBukkit on Bukkit 1.20.1-R0.1: WorldEdit 7.2.15; Vault 1.7.3-b131;Essentials".to_string();
+    assert_eq!(data.plugin_list(), [
+        plugin("WorldEdit", Some("7.2.15")), plugin("Vault", Some("1.7.3-b131")), plugin("Essentials", None),
+    ]);
+    for plugins in ["", "PocketMine-MP 4.0.0", "Nukkit 1.0: "] {
+        data.plugins = plugins.to_string();
+        assert!(data.plugin_list().is_empty(), "{}", plugins);
+    }
+    // PocketMine ends the list with a `;`, its plugins put a `v` in front of their versions
+    data.plugins = "PocketMine-MP 5.3.1: EconomyAPI v5.7.2; PurePerms 1.4.3;".to_string();
+    assert_eq!(data.plugin_list(), [plugin("EconomyAPI", Some("5.7.2")), plugin("PurePerms", Some("1.4.3"))]);
+    data.server_software = "Nukkit".to_string();
+    data.plugins = "Nukkit 1.0-SNAPSHOT: ServerTools v1.2; LuckPerms 5.4.0; ".to_string();
+    assert_eq!(data.plugin_list(), [plugin("ServerTools", Some("1.2")), plugin("LuckPerms", Some("5.4.0"))]);
+    // Bukkit versions are kept as sent
+    data.server_software = "CraftBukkit".to_string();
+    data.game_name = "MINECRAFT".to_string();
+    data.plugins = "CraftBukkit on Bukkit 1.20.1: Multiverse v4.3; ".to_string();
+    assert_eq!(data.plugin_list(), [plugin("Multiverse", Some("v4.3"))]);
+}
+
+#[test]
+fn software_family() {
+    use crate::model::SoftwareFamily;
+    let mut data = long_query_data();
+    assert_eq!(data.software_family(), SoftwareFamily::Bedrock);
+    for (engine, game_id, expected) in [
+        ("Paper", "MINECRAFT", SoftwareFamily::Paper), ("Purpur on Bukkit", "MINECRAFT", SoftwareFamily::Paper),
+        ("git-Spigot-79a30d7", "MINECRAFT", SoftwareFamily::Spigot), ("CraftBukkit", "MINECRAFT", SoftwareFamily::Bukkit),
+        ("", "MINECRAFT", SoftwareFamily::Vanilla), ("Nukkit", "MINECRAFTPE", SoftwareFamily::Bedrock),
+        ("", "minecraftpe", SoftwareFamily::Bedrock), ("Custom", "MINECRAFT", SoftwareFamily::Unknown),
+    ] {
+        data.server_software = engine.to_string();
+        data.game_name = game_id.to_string();
+        assert_eq!(data.software_family(), expected, "{} {}", engine, game_id);
+    }
+}
+
+#[tokio::test]
+async fn from_socket() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let socket = std::sync::Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = Client::from_socket(socket.clone(), addr);
+    assert_eq!(client.raknet_ping().await?.player_count, 3);
+    assert_eq!(client.router.local_addr()?, socket.local_addr()?);
+    Ok(())
+}
+
+#[cfg(feature = "tracing")]
+#[tokio::test]
+async fn tracing_events() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    use tracing::{span, Event, Metadata, Subscriber};
+    /// Collects the message of every event.
+    struct Messages(Arc<Mutex<Vec<String>>>);
+    impl Subscriber for Messages {
+        fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id { span::Id::from_u64(1) }
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, event: &Event<'_>) {
+            struct Message<'a>(&'a mut String);
+            impl tracing::field::Visit for Message<'_> {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "message" {
+                        *self.0 = format!("{:?}", value);
+                    }
+                }
+            }
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            self.0.lock().unwrap().push(message);
+        }
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let _guard = tracing::subscriber::set_default(Messages(messages.clone()));
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    Client::new(addr).await?.raknet_ping().await?;
+    assert_eq!(*messages.lock().unwrap(), ["sent request", "received response", "parse started", "parse finished"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn no_trailing_nul() -> Result<()> {
+    let addr = mock_server(|_| pong("MCPE;Dedicated Server;527;1.19.1;3;10;13253860892328930865;Bedrock level;Survival;1;19132;19133")).await?;
+    let data = Client::new(addr).await?.raknet_ping().await?;
+    assert_eq!(data.port_v6, Some(19133));
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &["Steve", "Alex"])).await?;
+    let data = Client::new(addr).await?.long_query().await?;
+    assert_eq!(data.players, ["Steve", "Alex"]);
+    assert!(data.extra.is_empty());
+    Ok(())
+}
+
+/// Spawns a SOCKS5 proxy accepting one client with the given credentials, relaying its udp datagrams.
+#[cfg(feature = "socks5")]
+async fn socks5_proxy(username: &'static str, password: &'static str) -> Result<std::net::SocketAddr> {
+    use tokio::io::AsyncReadExt;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut greeting = [0u8; 3];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(greeting, [0x05, 0x01, 0x02]);
+        stream.write_all(&[0x05, 0x02]).await.unwrap();
+        let mut auth = vec![0u8; 2];
+        stream.read_exact(&mut auth).await.unwrap();
+        auth.resize(auth.len() + auth[1] as usize + 1, 0);
+        stream.read_exact(&mut auth[2..]).await.unwrap();
+        let len = auth.len();
+        auth.resize(len + auth[len - 1] as usize, 0);
+        stream.read_exact(&mut auth[len..]).await.unwrap();
+        let expected = [&[0x01, username.len() as u8], username.as_bytes(), &[password.len() as u8], password.as_bytes()].concat();
+        if auth != expected {
+            stream.write_all(&[0x01, 0x01]).await.unwrap();
+            return;
+        }
+        stream.write_all(&[0x01, 0x00]).await.unwrap();
+        let mut request = [0u8; 10];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(request[1], 0x03);
+        let relay = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = relay.local_addr().unwrap().port().to_be_bytes();
+        // an unspecified relay address, meaning the proxy's own address
+        stream.write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, port[0], port[1]]).await.unwrap();
+        let mut client = None;
+        let mut buf = [0u8; 2048];
+        loop {
+            let (len, from) = relay.recv_from(&mut buf).await.unwrap();
+            if client.is_none() || client == Some(from) {
+                client = Some(from);
+                let to = std::net::SocketAddr::from(([buf[4], buf[5], buf[6], buf[7]], u16::from_be_bytes([buf[8], buf[9]])));
+                relay.send_to(&buf[10..len], to).await.unwrap();
+            } else if let (Some(client), std::net::SocketAddr::V4(from)) = (client, from) {
+                let header = [&[0x00, 0x00, 0x00, 0x01][..], &from.ip().octets(), &from.port().to_be_bytes()].concat();
+                relay.send_to(&[&header, &buf[..len]].concat(), client).await.unwrap();
+            }
+        }
+    });
+    Ok(addr)
+}
+
+#[cfg(feature = "socks5")]
+#[tokio::test]
+async fn socks5() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let proxy = socks5_proxy("user", "hunter2").await?;
+    let client = crate::ClientBuilder::new().timeout(Duration::from_secs(1))
+        .socks5_proxy(proxy).socks5_credentials("user", "hunter2")
+        .build(addr).await?;
+    let data = client.raknet_ping().await?;
+    assert_eq!(data.player_count, 3);
+    assert_eq!(data.remote, Some(addr));
+    // a dual-stack socket reports the relay's replies as coming from its mapped address
+    let proxy = socks5_proxy("user", "hunter2").await?;
+    let client = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).bind("[::]:0".parse().unwrap())
+        .socks5_proxy(proxy).socks5_credentials("user", "hunter2")
+        .build(addr).await?;
+    assert_eq!(client.raknet_ping().await?.player_count, 3);
+    let proxy = socks5_proxy("user", "hunter2").await?;
+    let result = crate::ClientBuilder::new().socks5_proxy(proxy).socks5_credentials("user", "wrong")
+        .build(addr).await;
+    assert!(matches!(result, Err(crate::Error::Io(e)) if e.kind() == std::io::ErrorKind::PermissionDenied));
+    Ok(())
+}
+
+#[tokio::test]
+async fn discards_other_sources() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let spoofer = UdpSocket::bind("127.0.0.1:0").await?;
+    let addr = server.local_addr()?;
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1024];
+        while let Ok((_, from)) = server.recv_from(&mut buf).await {
+            let _ = spoofer.send_to(&pong("MCPE;Spoofed;527;1.19.1;99;100;1;"), from).await;
+            let _ = server.send_to(&pong(BDS_PONG), from).await;
+        }
+    });
+    let client = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).build(addr).await?;
+    let data = client.raknet_ping().await?;
+    assert_eq!(data.motd_line1(), "Dedicated Server");
+    assert_eq!(data.remote, Some(addr));
+    Ok(())
+}
+
+#[tokio::test]
+async fn dual_stack_socket() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    // replies to an IPv4 remote come from its IPv4-mapped address on a dual-stack socket
+    let socket = std::sync::Arc::new(UdpSocket::bind("[::]:0").await?);
+    let client = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).build_with_socket(socket, addr);
+    assert_eq!(client.raknet_ping().await?.remote, Some(addr));
+    Ok(())
+}
+
+#[tokio::test]
+async fn ipv6_remote() -> Result<()> {
+    let v6 = mock_server_at("[::1]:0", |_| pong(BDS_PONG)).await?;
+    let v4 = mock_server(|_| pong(BDS_PONG)).await?;
+    let client = Client::new(v6).await?;
+    assert!(client.router.local_addr()?.is_ipv6());
+    assert_eq!(client.raknet_ping().await?.remote, Some(v6));
+    // one worker has to rebind when switching families
+    let builder = crate::ClientBuilder::new().timeout(Duration::from_secs(1));
+    for (addr, pong) in builder.ping_many(&[v4, v6, v4], 1).await {
+        assert_eq!(pong?.remote, Some(addr));
+    }
+    Ok(())
+}
+
+#[test]
+fn edition() -> Result<()> {
+    use crate::model::{Edition, QueryData};
+    let mut data = crate::parse::parse_pong(&pong(BDS_PONG))?;
+    assert_eq!(data.edition(), Edition::Bedrock);
+    data.game_edition = "MCEE".to_string();
+    assert_eq!(data.edition(), Edition::Education);
+    data.game_edition = "MCXX".to_string();
+    assert_eq!(data.edition(), Edition::Unknown);
+    assert_eq!(QueryData::edition(&data), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_response() -> Result<()> {
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let len = pong(BDS_PONG).len();
+    let client = crate::ClientBuilder::new().max_response(len).build(addr).await?;
+    assert_eq!(client.raknet_ping().await?.player_count, 3);
+    let client = crate::ClientBuilder::new().max_response(len - 1).timeout(Duration::from_millis(100)).build(addr).await?;
+    let err = client.raknet_ping().await.unwrap_err();
+    assert!(matches!(err, crate::Error::Timeout), "{:?}", err);
+    // an oversized datagram ahead of the reply is skipped
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let addr = server.local_addr()?;
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1024];
+        while let Ok((_, from)) = server.recv_from(&mut buf).await {
+            let _ = server.send_to(&[0x1C; 2048], from).await;
+            let _ = server.send_to(&pong(BDS_PONG), from).await;
+        }
+    });
+    let client = crate::ClientBuilder::new().max_response(len).timeout(Duration::from_secs(1)).build(addr).await?;
+    assert_eq!(client.raknet_ping().await?.player_count, 3);
+    Ok(())
+}
+
+#[test]
+fn socket_addr() {
+    let mut data = long_query_data();
+    assert_eq!(data.socket_addr(), Some("127.0.0.1:19132".parse().unwrap()));
+    data.host_ip = "[::1]".to_string();
+    assert_eq!(data.socket_addr(), Some("[::1]:19132".parse().unwrap()));
+    data.host_ip = "2001:db8::1".to_string();
+    assert_eq!(data.socket_addr(), Some("[2001:db8::1]:19132".parse().unwrap()));
+    data.host_ip = "play.example.com".to_string();
+    assert_eq!(data.socket_addr(), None);
+}
+
+#[tokio::test]
+async fn gen_challenge_token_masked() -> Result<()> {
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &[])).await?;
+    let (session, token) = Client::new(addr).await?.gen_challenge_token_masked(0x7172_7374).await?;
+    assert_eq!((session, token), (0x0102_0304, 9513307));
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancellable() -> Result<()> {
+    let addr = mock_server(|_| Vec::new()).await?;
+    let client = Client::new(addr).await?;
+    let result = crate::cancellable(client.raknet_ping(), tokio::time::sleep(Duration::from_millis(20))).await;
+    assert!(matches!(result, Err(crate::Error::Cancelled)));
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let client = Client::new(addr).await?;
+    let data = crate::cancellable(client.raknet_ping(), std::future::pending::<()>()).await?;
+    assert_eq!(data.player_count, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_stat_response() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let addr = server.local_addr()?;
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1024];
+        while let Ok((len, from)) = server.recv_from(&mut buf).await {
+            let req = &buf[..len];
+            if req[2] == 0x09 {
+                let _ = server.send_to(&[&[0x09], &req[3..7], &b"9513307\0"[..]].concat(), from).await;
+            } else {
+                // the late response to an earlier query arrives right before the real one
+                let _ = server.send_to(&full_stat(&[0x0F, 0x0F, 0x0F, 0x0F], &[("hostname", "Stale")], &[]), from).await;
+                let _ = server.send_to(&full_stat(&req[3..7], &POCKETMINE_KV, &[]), from).await;
+            }
+        }
+    });
+    let data = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).build(addr).await?
+        .long_query_with_session(0x0102_0304).await?;
+    assert_eq!(data.host_name, "A PocketMine Server");
+    Ok(())
+}
+
+/// Keeps other tests from changing the default timeout until dropped, then resets it (even if the test panicked).
+struct DefaultTimeout {
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+impl DefaultTimeout {
+    fn lock() -> Self {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        DefaultTimeout { _lock: LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) }
+    }
+}
+
+impl Drop for DefaultTimeout {
+    fn drop(&mut self) {
+        crate::ClientBuilder::set_default_timeout(None);
+    }
+}
+
+#[tokio::test]
+async fn default_timeout() -> Result<()> {
+    let _default = DefaultTimeout::lock();
+    // long enough to not bother other tests building clients meanwhile
+    crate::ClientBuilder::set_default_timeout(Some(Duration::from_secs(60)));
+    let client = Client::new("127.0.0.1:19132").await?;
+    let overridden = crate::ClientBuilder::new().timeout(Duration::from_secs(1)).build("127.0.0.1:19132").await?;
+    crate::ClientBuilder::set_default_timeout(None);
+    assert_eq!(client.timeout, Some(Duration::from_secs(60)));
+    assert_eq!(overridden.timeout, Some(Duration::from_secs(1)));
+    assert_eq!(Client::new("127.0.0.1:19132").await?.timeout, None);
+    Ok(())
+}
+
+#[test]
+fn parsers_never_panic() {
+    use crate::parse::{parse_challenge_token, parse_full_stat, parse_pong, parse_short_stat};
+    use rand::{Rng, SeedableRng};
+    let parse_all = |bytes: &[u8]| {
+        let _ = parse_pong(bytes);
+        let _ = parse_challenge_token(bytes);
+        let _ = parse_short_stat(bytes);
+        let _ = parse_full_stat(bytes);
+    };
+    let mut short_stat = b"\0\x01\x02\x03\x04motd\0SMP\0world\x002\x0020\0".to_vec();
+    short_stat.extend_from_slice(&19132u16.to_le_bytes());
+    short_stat.extend_from_slice(b"127.0.0.1\0");
+    // the hostname value isn't UTF-8
+    let mut non_utf8 = full_stat(&[1, 2, 3, 4], &POCKETMINE_KV, &["Steve"]);
+    non_utf8[crate::parse::FULL_STAT_HEADER_LEN + b"hostname\0".len()] = 0xff;
+    let valid = [
+        pong(BDS_PONG), pong_guid_after_magic(BDS_PONG), b"\x09\x01\x02\x03\x04123456\0".to_vec(), short_stat,
+        full_stat(&[1, 2, 3, 4], &POCKETMINE_KV, &["Steve", "Alex"]), non_utf8,
+    ];
+    // every truncation of a valid response, then the same responses with random bytes flipped
+    for response in &valid {
+        for len in 0..=response.len() {
+            parse_all(&response[..len]);
+        }
+    }
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0x5EED);
+    for _ in 0..2000 {
+        let mut response = valid[rng.gen_range(0..valid.len())].clone();
+        for _ in 0..rng.gen_range(1..8) {
+            let i = rng.gen_range(0..response.len());
+            response[i] = rng.gen();
+        }
+        parse_all(&response);
+    }
+    assert!(parse_full_stat(&valid[4]).is_ok());
+    assert_eq!(parse_full_stat(&valid[5]).unwrap().host_name, "\u{FFFD} PocketMine Server");
+}
+
+#[tokio::test]
+async fn raw_responses() -> Result<()> {
+    // the pong is missing its server id string, so it only fails once parsed
+    let broken = pong(BDS_PONG)[..40].to_vec();
+    let reply = broken.clone();
+    let addr = mock_server(move |_| reply.clone()).await?;
+    let client = Client::new(addr).await?;
+    assert_eq!(client.raknet_ping_raw().await?, broken);
+    assert!(client.raknet_ping().await.is_err());
+    let addr = gs3_server(|session| full_stat(session, &POCKETMINE_KV, &["Steve"])).await?;
+    let client = Client::new(addr).await?;
+    let raw = client.long_query_raw().await?;
+    assert_eq!(raw[5..], full_stat(&[0; 4], &POCKETMINE_KV, &["Steve"])[5..]);
+    assert_eq!(crate::parse::parse_full_stat(&raw)?.players, ["Steve"]);
+    Ok(())
+}
+
+/// An in-memory transport answering every datagram with a pong from its target.
+#[derive(Default)]
+struct PongTransport {
+    replies: std::sync::Mutex<std::collections::VecDeque<(Vec<u8>, std::net::SocketAddr)>>,
+    waker: std::sync::Mutex<Option<std::task::Waker>>,
+}
+
+impl crate::AsyncDatagram for PongTransport {
+    fn poll_send_to(&self, _: &mut std::task::Context<'_>, buf: &[u8], target: std::net::SocketAddr) -> std::task::Poll<std::io::Result<usize>> {
+        self.replies.lock().unwrap().push_back((pong(BDS_PONG), target));
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+        std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_recv_from(&self, cx: &mut std::task::Context<'_>, buf: &mut [u8]) -> std::task::Poll<std::io::Result<(usize, std::net::SocketAddr)>> {
+        match self.replies.lock().unwrap().pop_front() {
+            Some((datagram, from)) => {
+                // like udp, whatever doesn't fit the buffer is lost
+                let len = datagram.len().min(buf.len());
+                buf[..len].copy_from_slice(&datagram[..len]);
+                std::task::Poll::Ready(Ok((len, from)))
+            },
+            None => {
+                *self.waker.lock().unwrap() = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            },
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<std::net::SocketAddr> {
+        Ok("127.0.0.1:1".parse().unwrap())
+    }
+}
+
+#[tokio::test]
+async fn custom_transport() -> Result<()> {
+    let transport = std::sync::Arc::new(PongTransport::default());
+    let client = Client::from_transport(transport.clone(), "127.0.0.1:19132");
+    assert_eq!(client.raknet_ping().await?.remote, Some("127.0.0.1:19132".parse().unwrap()));
+    // an unread reply is drained by the next query
+    transport.replies.lock().unwrap().push_back((pong("MCPE;Stale;527;1.19.1;0;10;1"), "127.0.0.1:19132".parse().unwrap()));
+    assert_eq!(client.raknet_ping().await?.motd_line1(), "Dedicated Server");
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_players_listed() -> Result<()> {
+    let mut kv = POCKETMINE_KV;
+    kv[7] = ("numplayers", "3");
+    let addr = gs3_server(move |session| full_stat(session, &kv, &["Steve", "Alex", "Notch"])).await?;
+    let data = crate::ClientBuilder::new().max_players_listed(2).build(addr).await?.long_query().await?;
+    assert_eq!(data.players, ["Steve", "Alex"]);
+    assert_eq!(data.player_count, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn is_online() -> Result<()> {
+    let builder = crate::ClientBuilder::new().timeout(Duration::from_millis(50));
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    assert!(builder.clone().build(addr).await?.is_online().await);
+    // a pong too short to parse still means the server is up
+    let addr = mock_server(|_| pong(BDS_PONG)[..40].to_vec()).await?;
+    assert!(builder.clone().build(addr).await?.is_online().await);
+    let addr = mock_server(|_| b"\x09nope".to_vec()).await?;
+    assert!(!builder.clone().build(addr).await?.is_online().await);
+    let addr = mock_server(|_| Vec::new()).await?;
+    assert!(!builder.build(addr).await?.is_online().await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_outcome() -> Result<()> {
+    use crate::model::QueryOutcome;
+    let builder = crate::ClientBuilder::new().timeout(Duration::from_millis(50));
+    let addr = mock_server(|_| pong(BDS_PONG)).await?;
+    let outcome = builder.clone().build(addr).await?.raknet_ping_outcome().await?;
+    assert_eq!(outcome.online().map(|pong| pong.player_count), Some(3));
+    let addr = mock_server(|_| Vec::new()).await?;
+    assert!(!builder.clone().build(addr).await?.raknet_ping_outcome().await?.is_online());
+    // a server answering with garbage is up, but broken
+    let addr = mock_server(|_| b"\x09nope".to_vec()).await?;
+    assert!(builder.build(addr).await?.raknet_ping_outcome().await.is_err());
+    let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+    assert_eq!(QueryOutcome::<()>::from_result(Err(refused.into()))?, QueryOutcome::Offline);
+    assert!(QueryOutcome::<()>::from_result(Err(crate::Error::MissingField("hostname"))).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn legacy_stat() -> Result<()> {
+    // a pre-GS4 server that ignores handshakes and only answers stat requests without a token
+    let addr = mock_server(|req| match (req[2], req.len()) {
+        (0x00, 7) => {
+            let mut stat = [&[0x00], &req[3..7], &b"A Legacy Server\0SMP\0world\x000\x0010\0"[..]].concat();
+            stat.extend_from_slice(&19132u16.to_le_bytes());
+            stat.extend_from_slice(b"127.0.0.1\0");
+            stat
+        },
+        (0x00, 11) if req[7..] == crate::packet::LEGACY_FULL_STAT_FLAGS => full_stat(&req[3..7], &POCKETMINE_KV, &["Steve"]),
+        _ => Vec::new(),
+    }).await?;
+    let client = crate::ClientBuilder::new().legacy(true).timeout(Duration::from_millis(50)).build(addr).await?;
+    assert_eq!(client.short_query().await?.motd, "A Legacy Server");
+    assert_eq!(client.long_query().await?.players, ["Steve"]);
+    let client = crate::ClientBuilder::new().timeout(Duration::from_millis(50)).build(addr).await?;
+    assert!(matches!(client.short_query().await, Err(crate::Error::Timeout)));
+    Ok(())
+}
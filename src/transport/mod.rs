@@ -0,0 +1,76 @@
+//! The datagram transport a [Client](crate::Client) sends its requests and receives replies over.
+//!
+//! Clients use a tokio [UdpSocket] unless they're given another [AsyncDatagram], e.g. one
+//! forwarding datagrams through a transport the host environment provides. Only the datagrams
+//! go through the transport, a client still needs a tokio runtime for everything else.
+
+use std::future::poll_fn;
+use std::io::{ErrorKind, Result};
+use std::net::SocketAddr;
+use std::task::{Context, Poll, Waker};
+use tokio::io::ReadBuf;
+use tokio::net::UdpSocket;
+
+/// A connectionless, datagram based transport, modeled after tokio's [UdpSocket].
+///
+/// Methods are poll based so implementations don't depend on a particular runtime, and
+/// registering to be woken up when they return [Poll::Pending] is up to the implementation.
+/// Each received datagram has to be exactly one datagram the remote sent, with the remote's
+/// address as its source, since replies are matched to requests by that address.
+///
+/// The client using the transport is still bound to tokio though: it spawns the task receiving
+/// datagrams with [tokio::spawn], times queries out with [tokio::time] and resolves remotes (any
+/// [tokio::net::ToSocketAddrs]) with [tokio::net::lookup_host]. Queries have to run on a tokio runtime
+/// with the time driver enabled, whatever the transport.
+pub trait AsyncDatagram: Send + Sync {
+    /// Attempts to send the datagram to the target, returning how many bytes were sent.
+    fn poll_send_to(&self, cx: &mut Context<'_>, buf: &[u8], target: SocketAddr) -> Poll<Result<usize>>;
+
+    /// Attempts to receive a datagram into the buffer, returning its length and where it came from.
+    fn poll_recv_from(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<(usize, SocketAddr)>>;
+
+    /// The local address datagrams are sent from, used for packet captures.
+    fn local_addr(&self) -> Result<SocketAddr>;
+
+    /// Receives a datagram that's already queued, failing with [ErrorKind::WouldBlock] if there's none.
+    ///
+    /// Used to route the replies queued before sending a request. It's called until it fails, so
+    /// once the queue is empty it has to return an error, [ErrorKind::WouldBlock] or any other,
+    /// rather than blocking or receiving forever. The default polls
+    /// [poll_recv_from](AsyncDatagram::poll_recv_from) once without anything to wake up.
+    fn try_recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr)> {
+        match self.poll_recv_from(&mut Context::from_waker(Waker::noop()), buf) {
+            Poll::Ready(result) => result,
+            Poll::Pending => Err(ErrorKind::WouldBlock.into()),
+        }
+    }
+}
+
+impl AsyncDatagram for UdpSocket {
+    fn poll_send_to(&self, cx: &mut Context<'_>, buf: &[u8], target: SocketAddr) -> Poll<Result<usize>> {
+        UdpSocket::poll_send_to(self, cx, buf, target)
+    }
+
+    fn poll_recv_from(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<(usize, SocketAddr)>> {
+        let mut read = ReadBuf::new(buf);
+        UdpSocket::poll_recv_from(self, cx, &mut read).map_ok(|from| (read.filled().len(), from))
+    }
+
+    fn local_addr(&self) -> Result<SocketAddr> {
+        UdpSocket::local_addr(self)
+    }
+
+    fn try_recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr)> {
+        UdpSocket::try_recv_from(self, buf)
+    }
+}
+
+/// Sends the datagram to the target over the transport.
+pub(crate) async fn send_to(transport: &dyn AsyncDatagram, buf: &[u8], target: SocketAddr) -> Result<usize> {
+    poll_fn(|cx| transport.poll_send_to(cx, buf, target)).await
+}
+
+/// Receives the next datagram from the transport.
+pub(crate) async fn recv_from(transport: &dyn AsyncDatagram, buf: &mut [u8]) -> Result<(usize, SocketAddr)> {
+    poll_fn(|cx| transport.poll_recv_from(cx, buf)).await
+}
@@ -1,9 +1,18 @@
-use std::io::Read;
-use tokio::io::AsyncBufReadExt;
+use alloc::format;
+use alloc::string::String;
+use core::net::{IpAddr, SocketAddr};
+use core::time::Duration;
 
+/// Returns the index of the first occurrence of needle in buf.
+///
+/// An empty needle never matches, as searching for one is always a bug in the caller.
+/// Neither does a buffer shorter than the needle, e.g. a truncated response.
 pub fn slice_index<T>(buf: &[T], needle: &[T]) -> Option<usize>
 where T: Clone + PartialEq
 {
+    if needle.is_empty() || buf.len() < needle.len() {
+        return None;
+    }
     for i in 0..=buf.len() - needle.len() {
         if buf[i..].starts_with(needle) {
             return Some(i);
@@ -12,8 +21,97 @@ where T: Clone + PartialEq
     None
 }
 
-pub async fn read_nulltermed_str<R: Read + Sync + AsyncBufReadExt + Unpin>(buf: &mut R) -> Result<String, std::io::Error> {
-    let mut temp = vec![];
-    buf.read_until(0x00, &mut temp).await?;
-    Ok( String::from_utf8_lossy(&temp.as_slice()[0..temp.len()-1]).to_string())
-}
\ No newline at end of file
+/// 64 bit FNV-1a, used where a hash has to stay the same across runs and platforms.
+#[cfg(feature = "std")]
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Resolves the remote to its first address.
+#[cfg(feature = "std")]
+pub async fn resolve<A: tokio::net::ToSocketAddrs>(remote: A) -> crate::Result<SocketAddr> {
+    Ok(tokio::net::lookup_host(remote).await?.next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Remote did not resolve to an address"))?)
+}
+
+/// Whether the addresses are the same, taking an IPv4-mapped IPv6 address for the IPv4 one it maps.
+///
+/// Dual-stack sockets can send to an IPv4 address but report the reply as coming from the mapped one.
+#[cfg(feature = "std")]
+pub fn same_addr(a: SocketAddr, b: SocketAddr) -> bool {
+    (a.ip().to_canonical(), a.port()) == (b.ip().to_canonical(), b.port())
+}
+
+/// Combines a self-reported host ip and port into an address, None if the ip isn't an ip literal.
+///
+/// IPv6 hosts may be wrapped in brackets, some servers report them like that.
+pub fn host_addr(ip: &str, port: u16) -> Option<SocketAddr> {
+    let ip = ip.trim();
+    let ip = ip.strip_prefix('[').and_then(|ip| ip.strip_suffix(']')).unwrap_or(ip);
+    ip.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, port))
+}
+
+/// Parses a player count, treating a blank count as 0 since some proxies send one while their backend is down.
+pub fn parse_count(count: &str, field: &'static str) -> crate::Result<usize> {
+    match count {
+        "" => Ok(0),
+        count => count.parse().map_err(|_| crate::Error::ParseField { field }),
+    }
+}
+
+/// Reads a string up to its null terminator, advancing the buffer past it.
+///
+/// A string cut off by the end of the buffer (a truncated response) is returned as far as it got.
+pub fn read_nulltermed_str(buf: &mut &[u8]) -> String {
+    let (string, rest) = match buf.iter().position(|byte| *byte == 0x00) {
+        Some(terminator) => (&buf[..terminator], &buf[terminator + 1..]),
+        None => (*buf, &[][..]),
+    };
+    *buf = rest;
+    String::from_utf8_lossy(string).into_owned()
+}
+
+/// Runs the query until it finishes or the cancel future completes, in which case it fails with
+/// [Error::Cancelled](crate::Error::Cancelled).
+///
+/// Any future works for cancelling, e.g. `tokio_util`'s `CancellationToken::cancelled()` or a
+/// shutdown channel. Queries are cancellation safe (see [Client](crate::Client)), so the client
+/// can keep being used after one of its queries got cancelled.
+///
+/// # [Example]
+/// ```no_run
+/// # use rsquery::Client;
+/// # #[tokio::main]
+/// # async fn main() -> rsquery::Result<()> {
+/// let client = Client::new("ip:port").await?;
+/// let (shutdown, cancelled) = tokio::sync::oneshot::channel::<()>();
+/// tokio::spawn(async move {
+///     tokio::signal::ctrl_c().await.ok();
+///     shutdown.send(()).ok();
+/// });
+/// match rsquery::cancellable(client.long_query(), cancelled).await {
+///     Err(rsquery::Error::Cancelled) => println!("shutting down"),
+///     result => println!("{:?}", result?),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub async fn cancellable<T, Q, C>(query: Q, cancel: C) -> crate::Result<T>
+where Q: std::future::Future<Output = crate::Result<T>>, C: std::future::Future
+{
+    tokio::select! {
+        result = query => result,
+        _ = cancel => Err(crate::Error::Cancelled),
+    }
+}
+
+/// Formats a latency for logging, as whole milliseconds below a second (`"12ms"`)
+/// and as seconds with one decimal from there on (`"1.2s"`).
+pub fn fmt_latency(latency: &Duration) -> String {
+    if latency.as_millis() < 1000 {
+        format!("{}ms", latency.as_millis())
+    } else {
+        format!("{:.1}s", latency.as_secs_f64())
+    }
+}